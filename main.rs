@@ -1,29 +1,68 @@
-//! Blinks the LED on a Pico board
+//! Explores a maze on the robot
 //!
-//! This will blink an LED attached to GP25, which is the pin the Pico uses for the on-board LED.
+//! Drives the mouse from the start to the goal of a classic maze using the wall sensors, motors,
+//! wheel encoders and gyroscope of the robot, and turns on the LED on GP25 once it is done.
 #![no_std]
 #![no_main]
 
 use bsp::entry;
-use defmt_rtt as _;
-use embedded_hal::digital::OutputPin;
-#[cfg(not(test))]
-use panic_probe as _;
-use rp_pico as bsp;
 use bsp::hal::{
-    clocks::{init_clocks_and_plls, Clock},
+    adc::{Adc, AdcPin},
+    clocks::init_clocks_and_plls,
+    fugit::RateExtU32,
+    gpio::{self, Interrupt},
     pac,
+    pac::interrupt,
+    pwm::Slices,
     sio::Sio,
     watchdog::Watchdog,
+    Timer, I2C,
+};
+use core::cell::RefCell;
+use critical_section::Mutex;
+use defmt::{error, info, Debug2Format};
+use defmt_rtt as _;
+use embedded_hal::digital::{InputPin, OutputPin};
+use mouse::control::Tuning;
+use mouse::hal::explorer::Explorer;
+use mouse::hal::rp2040::{Calibration, Encoders, IrSensors, Motors, Mpu6050, Wheel};
+use mouse::hal::{DriveMotors, Hardware};
+use mouse::maze::ClassicMaze;
+use mouse::physics::{Robot, Wheels};
+#[cfg(not(test))]
+use panic_probe as _;
+use rp_pico as bsp;
+
+/// The period of the control loop in seconds.
+const DT: f64 = 0.001;
+/// The distance a wheel travels per encoder tick, for 12 ticks per motor turn, a 30:1 gearbox
+/// and a wheel radius of 12 mm.
+const METERS_PER_TICK: f64 = 2. * core::f64::consts::PI * 0.012 / (12. * 30.);
+/// The calibration of the infrared wall sensors.
+const CALIBRATION: Calibration = Calibration {
+    scale: 5.,
+    min_reading: 200,
 };
-use defmt::info;
+
+/// The ticks of both wheels, counted by [IO_IRQ_BANK0].
+static ENCODERS: Encoders = Encoders::new(METERS_PER_TICK);
+
+/// The A and B channel of the left and right encoder.
+type EncoderPins = (
+    gpio::Pin<gpio::bank0::Gpio6, gpio::FunctionSioInput, gpio::PullUp>,
+    gpio::Pin<gpio::bank0::Gpio7, gpio::FunctionSioInput, gpio::PullUp>,
+    gpio::Pin<gpio::bank0::Gpio8, gpio::FunctionSioInput, gpio::PullUp>,
+    gpio::Pin<gpio::bank0::Gpio9, gpio::FunctionSioInput, gpio::PullUp>,
+);
+
+/// The encoder pins, moved here once the interrupt is enabled.
+static ENCODER_PINS: Mutex<RefCell<Option<EncoderPins>>> = Mutex::new(RefCell::new(None));
 
 #[cfg(not(test))]
 #[entry]
 fn main() -> ! {
     info!("Program start");
     let mut pac = pac::Peripherals::take().unwrap();
-    let core = pac::CorePeripherals::take().unwrap();
     let mut watchdog = Watchdog::new(pac.WATCHDOG);
     let sio = Sio::new(pac.SIO);
 
@@ -41,7 +80,7 @@ fn main() -> ! {
     .ok()
     .unwrap();
 
-    let mut delay = cortex_m::delay::Delay::new(core.SYST, clocks.system_clock.freq().to_Hz());
+    let timer = Timer::new(pac.TIMER, &mut pac.RESETS, &clocks);
 
     let pins = bsp::Pins::new(
         pac.IO_BANK0,
@@ -50,23 +89,115 @@ fn main() -> ! {
         &mut pac.RESETS,
     );
 
-    // This is the correct pin on the Raspberry Pico board. On other boards, even if they have an
-    // on-board LED, it might need to be changed.
-    //
-    // Notably, on the Pico W, the LED is not connected to any of the RP2040 GPIOs but to the cyw43 module instead.
-    // One way to do that is by using [embassy](https://github.com/embassy-rs/embassy/blob/main/examples/rp/src/bin/wifi_blinky.rs)
-    //
-    // If you have a Pico W and want to toggle a LED with a simple GPIO output pin, you can connect an external
-    // LED to one of the GPIO pins, and reference that pin here. Don't forget adding an appropriate resistor
-    // in series with the LED.
-    let mut led_pin = pins.led.into_push_pull_output();
+    // the front, left and right infrared sensors on the ADC pins
+    let adc = Adc::new(pac.ADC, &mut pac.RESETS);
+    let sensors = IrSensors::new(
+        adc,
+        AdcPin::new(pins.gpio26.into_floating_input()).unwrap(),
+        AdcPin::new(pins.gpio27.into_floating_input()).unwrap(),
+        AdcPin::new(pins.gpio28.into_floating_input()).unwrap(),
+        CALIBRATION,
+    );
+
+    // both motors on a single PWM slice, with a direction pin each
+    let slices = Slices::new(pac.PWM, &mut pac.RESETS);
+    let mut pwm = slices.pwm1;
+    pwm.set_ph_correct();
+    pwm.enable();
+    let mut left = pwm.channel_a;
+    left.output_to(pins.gpio2);
+    let mut right = pwm.channel_b;
+    right.output_to(pins.gpio3);
+    let motors = Motors::new(
+        left,
+        right,
+        pins.gpio4.into_push_pull_output(),
+        pins.gpio5.into_push_pull_output(),
+    );
 
+    // the encoders tick on every rising edge of their A channel
+    let encoder_pins = (
+        pins.gpio6.into_pull_up_input(),
+        pins.gpio7.into_pull_up_input(),
+        pins.gpio8.into_pull_up_input(),
+        pins.gpio9.into_pull_up_input(),
+    );
+    encoder_pins.0.set_interrupt_enabled(Interrupt::EdgeHigh, true);
+    encoder_pins.2.set_interrupt_enabled(Interrupt::EdgeHigh, true);
+    critical_section::with(|cs| ENCODER_PINS.borrow(cs).replace(Some(encoder_pins)));
+    unsafe {
+        pac::NVIC::unmask(pac::Interrupt::IO_IRQ_BANK0);
+    }
+
+    let i2c = I2C::i2c0(
+        pac.I2C0,
+        pins.gpio0.reconfigure(),
+        pins.gpio1.reconfigure(),
+        400.kHz(),
+        &mut pac.RESETS,
+        &clocks.system_clock,
+    );
+    let gyro = Mpu6050::new(i2c).unwrap();
+
+    let mut robot = Hardware {
+        sensors,
+        motors,
+        encoders: &ENCODERS,
+        gyro,
+    };
+    let mut explorer = Explorer::new(
+        ClassicMaze::new(),
+        Robot::default().track,
+        &Tuning::default(),
+    );
+
+    let period = (DT * 1_000_000.) as u64;
+    let mut next = timer.get_counter().ticks();
     loop {
-        info!("on!");
-        led_pin.set_high().unwrap();
-        delay.delay_ms(500);
-        info!("off!");
-        led_pin.set_low().unwrap();
-        delay.delay_ms(500);
+        match explorer.update(&mut robot, DT) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => {
+                error!("Failed to explore: {}", Debug2Format(&e));
+                break;
+            }
+        }
+
+        next += period;
+        while timer.get_counter().ticks() < next {}
     }
+
+    robot.motors.set_duty(Wheels::default()).unwrap();
+    info!(
+        "Explored {} segments, in the goal: {}",
+        explorer.path().len(),
+        explorer.is_done()
+    );
+
+    let mut led_pin = pins.led.into_push_pull_output();
+    led_pin.set_high().unwrap();
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
+
+/// Counts the ticks of the wheel encoders.
+#[interrupt]
+fn IO_IRQ_BANK0() {
+    critical_section::with(|cs| {
+        let mut pins = ENCODER_PINS.borrow_ref_mut(cs);
+        let Some((left_a, left_b, right_a, right_b)) = pins.as_mut() else {
+            return;
+        };
+
+        // the B channel leads the A channel when the wheel turns forward
+        if left_a.interrupt_status(Interrupt::EdgeHigh) {
+            ENCODERS.tick(Wheel::Left, left_b.is_high().unwrap());
+            left_a.clear_interrupt(Interrupt::EdgeHigh);
+        }
+        if right_a.interrupt_status(Interrupt::EdgeHigh) {
+            ENCODERS.tick(Wheel::Right, right_b.is_high().unwrap());
+            right_a.clear_interrupt(Interrupt::EdgeHigh);
+        }
+    });
 }
@@ -0,0 +1,369 @@
+use mouse::dstar::DStarLite;
+use mouse::grid::{BitGrid, Grid};
+use mouse::maze::file::MazeFile;
+use mouse::maze::generate::{generate, Algorithm};
+use mouse::maze::{Assumption, ClassicMaze, Maze, Relative};
+use mouse::path::profile::Limits;
+use mouse::path::Path;
+use mouse::pathfinder::Scratch;
+use mouse::solver::{solve, Solver};
+use mouse::speedrun::{fastest_route, Costs};
+use mouse::vec::Vecu;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io::Write;
+use std::mem::size_of;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+const USAGE: &str = "\
+Usage: mouse-bench [OPTIONS]
+
+Runs every solver against a corpus of mazes and writes one CSV row per maze and solver.
+
+Options:
+  -d, --dir <DIR>          Add every .maz and ASCII maze file in <DIR> to the corpus
+  -g, --generate <N>       Add <N> generated classic mazes per algorithm [default: 10]
+  -l, --loopiness <L>      The loopiness of generated mazes [default: 0.1]
+  -s, --solver <SOLVER>    Only run <SOLVER>, may be repeated [default: all]
+  -o, --output <FILE>      Write the CSV to <FILE> instead of stdout
+  -h, --help               Print this help
+
+Memory columns:
+  heap_bytes               The most bytes a solver had allocated on the heap at once, which
+                           excludes the state it keeps on the stack
+  state_bytes              The size of the maze, paths and planner state a solver keeps while
+                           exploring, which does not depend on the maze
+";
+
+const HEADER: &str = "maze,width,height,solver,visited,moves,turns,search_time_s,\
+route_length,shortest,length_gap,run_time_s,reference_run_time_s,run_time_gap_s,\
+solve_us,heap_bytes,state_bytes";
+
+/// An allocator that keeps track of the amount of bytes allocated on the heap.
+struct Counting;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(current, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Runs `f`, and returns its result and the highest amount of bytes it had allocated at once.
+fn peak_heap<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let start = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(start, Ordering::Relaxed);
+    let result = f();
+    (result, PEAK.load(Ordering::Relaxed) - start)
+}
+
+/// The parsed command line arguments.
+struct Args {
+    dirs: Vec<String>,
+    generate: u64,
+    loopiness: f64,
+    solvers: Vec<Solver>,
+    output: Option<String>,
+}
+
+/// Parses the command line arguments, excluding the name of the binary.
+fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut args = args.into_iter();
+    let mut parsed = Args {
+        dirs: Vec::new(),
+        generate: 10,
+        loopiness: 0.1,
+        solvers: Vec::new(),
+        output: None,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-d" | "--dir" => parsed.dirs.push(value()?),
+            "-g" | "--generate" => {
+                let count = value()?;
+                parsed.generate = count
+                    .parse()
+                    .map_err(|_| format!("invalid amount '{}'", count))?;
+            }
+            "-l" | "--loopiness" => {
+                let loopiness = value()?;
+                parsed.loopiness = loopiness
+                    .parse()
+                    .map_err(|_| format!("invalid loopiness '{}'", loopiness))?;
+            }
+            "-s" | "--solver" => {
+                let name = value()?;
+                let solver =
+                    Solver::from_name(&name).ok_or(format!("unknown solver '{}'", name))?;
+                parsed.solvers.push(solver);
+            }
+            "-o" | "--output" => parsed.output = Some(value()?),
+            _ => return Err(format!("unexpected argument '{}'", arg)),
+        }
+    }
+
+    if parsed.solvers.is_empty() {
+        parsed.solvers = Solver::ALL.to_vec();
+    }
+    Ok(Some(parsed))
+}
+
+/// The result of a single solver on a single maze.
+struct Row {
+    maze: String,
+    width: usize,
+    height: usize,
+    solver: Solver,
+    visited: u16,
+    moves: u32,
+    turns: usize,
+    search_time: f64,
+    route_length: Option<u16>,
+    shortest: Option<u16>,
+    run_time: Option<f64>,
+    reference_run_time: Option<f64>,
+    solve_us: u128,
+    heap: usize,
+    state: usize,
+}
+
+/// Formats `value`, or an empty field if it is `None`.
+fn field<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// Formats `seconds` with millisecond precision, or an empty field if it is `None`.
+fn seconds(seconds: Option<f64>) -> String {
+    field(seconds.map(|seconds| format!("{:.3}", seconds)))
+}
+
+impl Row {
+    /// Returns the amount of segments the route is longer than the shortest route.
+    fn length_gap(&self) -> Option<u16> {
+        Some(self.route_length? - self.shortest?)
+    }
+
+    /// Returns the amount of seconds the speed run is slower than the reference run, which is
+    /// planned the same way through the maze with every wall known. This may be negative, as the
+    /// planner minimizes [Costs] rather than the estimated time.
+    fn run_time_gap(&self) -> Option<f64> {
+        Some(self.run_time? - self.reference_run_time?)
+    }
+
+    /// Writes this row as a line of CSV, in the order of [HEADER].
+    fn write(&self, out: &mut impl Write) -> std::io::Result<()> {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{:.3},{},{},{},{},{},{},{},{},{}",
+            self.maze,
+            self.width,
+            self.height,
+            self.solver.name(),
+            self.visited,
+            self.moves,
+            self.turns,
+            self.search_time,
+            field(self.route_length),
+            field(self.shortest),
+            field(self.length_gap()),
+            seconds(self.run_time),
+            seconds(self.reference_run_time),
+            seconds(self.run_time_gap()),
+            self.solve_us,
+            self.heap,
+            self.state
+        )
+    }
+}
+
+/// Returns the size in bytes of the state `solver` keeps while exploring a `W`x`H` maze, which
+/// is the known maze, the paths it drives and the state of its planner.
+fn state_bytes<const W: usize, const H: usize>(solver: Solver) -> usize {
+    let maze = size_of::<Maze<W, H>>();
+    let path = size_of::<Path<W, H>>();
+    let distances = size_of::<Grid<u16, W, H>>();
+
+    match solver {
+        Solver::FloodFill => maze + path + size_of::<Scratch<W, H>>(),
+        Solver::DStar => maze + path + size_of::<DStarLite<W, H>>(),
+        // the visited segments, and the distances from the start, the mouse and the target
+        Solver::Explore => maze + path + size_of::<BitGrid<W, H>>() + 3 * distances,
+        // the explored path, the next step, the way back and the route when stuck
+        Solver::Search => maze + 4 * path,
+    }
+}
+
+/// Runs every solver in `solvers` on `real`, and adds a row for each to `rows`.
+fn bench<const W: usize, const H: usize>(
+    name: &str,
+    real: &Maze<W, H>,
+    solvers: &[Solver],
+    rows: &mut Vec<Row>,
+) {
+    // the speed run as planned with every wall known
+    let limits = Limits::default();
+    let shortest = real.shortest_route(Vecu::new(), Assumption::Pessimistic);
+    let reference = fastest_route(real, Vecu::new(), Relative::South, &Costs::default())
+        .map(|route| route.path.profile(&limits).time());
+
+    for solver in solvers {
+        let start = Instant::now();
+        let (outcome, heap) = peak_heap(|| solve(real, *solver, &limits));
+        let solve_us = start.elapsed().as_micros();
+
+        rows.push(Row {
+            maze: name.to_string(),
+            width: W,
+            height: H,
+            solver: *solver,
+            visited: outcome.visited,
+            moves: outcome.moves(),
+            turns: outcome.explored.turns(),
+            search_time: outcome.search_time,
+            route_length: outcome
+                .route
+                .as_ref()
+                .map(|route| route.path.len() as u16 - 1),
+            shortest,
+            run_time: outcome.run_time,
+            reference_run_time: reference,
+            solve_us,
+            heap,
+            state: state_bytes::<W, H>(*solver),
+        });
+    }
+}
+
+/// Runs the solvers of `args` on every maze in the directories of `args`.
+fn bench_dirs(args: &Args, rows: &mut Vec<Row>) -> Result<(), String> {
+    for dir in &args.dirs {
+        let entries = std::fs::read_dir(dir).map_err(|e| format!("'{}': {}", dir, e))?;
+        let mut files: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .collect();
+        files.sort();
+
+        for file in files {
+            let name = file.display().to_string();
+            let bytes = std::fs::read(&file).map_err(|e| format!("'{}': {}", name, e))?;
+
+            match MazeFile::read(&bytes) {
+                Ok(MazeFile::Classic(maze)) => bench(&name, &*maze, &args.solvers, rows),
+                Ok(MazeFile::HalfSize(maze)) => bench(&name, &*maze, &args.solvers, rows),
+                Err(e) => eprintln!("skipping '{}': {}", name, e),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the average of every solver over all mazes in `rows` to stderr.
+fn summarize(rows: &[Row], solvers: &[Solver]) {
+    eprintln!(
+        "{:<10} {:>6} {:>6} {:>8} {:>8} {:>8} {:>10}",
+        "solver", "mazes", "solved", "moves", "gap", "run gap", "total ms"
+    );
+
+    for solver in solvers {
+        let rows: Vec<&Row> = rows.iter().filter(|row| row.solver == *solver).collect();
+        let solved: Vec<&&Row> = rows.iter().filter(|row| row.run_time.is_some()).collect();
+        let mean =
+            |values: &mut dyn Iterator<Item = f64>, n: usize| values.sum::<f64>() / n.max(1) as f64;
+
+        eprintln!(
+            "{:<10} {:>6} {:>6} {:>8.1} {:>8.2} {:>8.3} {:>10.1}",
+            solver.name(),
+            rows.len(),
+            solved.len(),
+            mean(&mut rows.iter().map(|row| row.moves as f64), rows.len()),
+            mean(
+                &mut solved
+                    .iter()
+                    .filter_map(|row| row.length_gap())
+                    .map(f64::from),
+                solved.len()
+            ),
+            mean(
+                &mut solved.iter().filter_map(|row| row.run_time_gap()),
+                solved.len()
+            ),
+            rows.iter().map(|row| row.solve_us).sum::<u128>() as f64 / 1000.
+        );
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match parse(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut rows = Vec::new();
+    if let Err(e) = bench_dirs(&args, &mut rows) {
+        eprintln!("error: failed to read {}", e);
+        return ExitCode::FAILURE;
+    }
+
+    for (name, algorithm) in [
+        ("backtracker", Algorithm::Backtracker),
+        ("prim", Algorithm::Prim),
+        ("kruskal", Algorithm::Kruskal),
+    ] {
+        for seed in 0..args.generate {
+            let maze: ClassicMaze = generate(algorithm, seed, args.loopiness);
+            let name = format!("{}-{}", name, seed);
+            bench(&name, &maze, &args.solvers, &mut rows);
+        }
+    }
+
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(path) => match std::fs::File::create(path) {
+            Ok(file) => Box::new(std::io::BufWriter::new(file)),
+            Err(e) => {
+                eprintln!("error: failed to create '{}': {}", path, e);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => Box::new(std::io::stdout().lock()),
+    };
+
+    let written = writeln!(out, "{}", HEADER)
+        .and_then(|_| rows.iter().try_for_each(|row| row.write(&mut out)))
+        .and_then(|_| out.flush());
+    if let Err(e) = written {
+        eprintln!("error: failed to write the CSV: {}", e);
+        return ExitCode::FAILURE;
+    }
+
+    summarize(&rows, &args.solvers);
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,176 @@
+use mouse::maze::file::MazeFile;
+use mouse::maze::Maze;
+use mouse::path::profile::Limits;
+use mouse::path::Path;
+use mouse::solver::{solve, Outcome, Solver};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: mouse-cli [OPTIONS] <MAZE>
+
+Explores the maze in <MAZE> in simulation, then plans the speed run.
+<MAZE> is a .maz file of 256 or 1024 bytes, or an ASCII maze of 16x16 or 32x32 segments.
+
+Options:
+  -s, --solver <SOLVER>  floodfill, dstar, explore or search [default: floodfill]
+  -f, --format <FORMAT>  text or json [default: text]
+  -h, --help             Print this help
+";
+
+/// The format of the output.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Format {
+    Text,
+    Json,
+}
+
+/// The parsed command line arguments.
+struct Args {
+    maze: String,
+    solver: Solver,
+    format: Format,
+}
+
+/// Parses the command line arguments, excluding the name of the binary.
+fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut args = args.into_iter();
+    let mut maze = None;
+    let mut solver = Solver::FloodFill;
+    let mut format = Format::Text;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-s" | "--solver" => {
+                let name = args.next().ok_or("missing value for --solver")?;
+                solver = Solver::from_name(&name).ok_or(format!("unknown solver '{}'", name))?;
+            }
+            "-f" | "--format" => {
+                format = match args.next().as_deref() {
+                    Some("text") => Format::Text,
+                    Some("json") => Format::Json,
+                    Some(other) => return Err(format!("unknown format '{}'", other)),
+                    None => return Err("missing value for --format".into()),
+                };
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+            _ if maze.is_some() => return Err(format!("unexpected argument '{}'", arg)),
+            _ => maze = Some(arg),
+        }
+    }
+
+    let maze = maze.ok_or("missing maze file")?;
+    Ok(Some(Args {
+        maze,
+        solver,
+        format,
+    }))
+}
+
+/// Returns the segments of `path`, as `[x, y]` pairs if `json` is set.
+fn segments<const W: usize, const H: usize>(path: &Path<W, H>, json: bool) -> String {
+    (0..path.len())
+        .map(|i| {
+            let pos = path.segment(i).unwrap();
+            if json {
+                format!("[{}, {}]", pos.x, pos.y)
+            } else {
+                format!("{:?}", pos)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(if json { ", " } else { " " })
+}
+
+/// Prints `outcome` of `solver` in `format`.
+fn print<const W: usize, const H: usize>(outcome: &Outcome<W, H>, solver: Solver, format: Format) {
+    let route = outcome.route.as_ref();
+
+    match format {
+        Format::Text => {
+            println!("solver:         {}", solver.name());
+            println!("maze:           {}x{}", W, H);
+            println!(
+                "cells explored: {} ({} moves)",
+                outcome.visited,
+                outcome.moves()
+            );
+            if outcome.truncated {
+                println!(
+                    "note:           only the first {} moves were recorded",
+                    outcome.moves()
+                );
+            }
+            println!("search time:    {:.3} s", outcome.search_time);
+            match (route, outcome.run_time) {
+                (Some(route), Some(run_time)) => {
+                    println!("path:           {}", segments(&route.path, false));
+                    println!("run time:       {:.3} s", run_time);
+                }
+                _ => println!("path:           goal not found"),
+            }
+        }
+        Format::Json => {
+            let path = route.map_or("null".into(), |route| {
+                format!("[{}]", segments(&route.path, true))
+            });
+            let run_time = outcome
+                .run_time
+                .map_or("null".into(), |time| format!("{:.3}", time));
+
+            println!(
+                "{{\"solver\": \"{}\", \"width\": {}, \"height\": {}, \"explored\": {}, \
+                 \"moves\": {}, \"truncated\": {}, \"search_time\": {:.3}, \"path\": {}, \
+                 \"run_time\": {}}}",
+                solver.name(),
+                W,
+                H,
+                outcome.visited,
+                outcome.moves(),
+                outcome.truncated,
+                outcome.search_time,
+                path,
+                run_time
+            );
+        }
+    }
+}
+
+/// Explores `maze` and plans the speed run, and prints the outcome.
+fn run<const W: usize, const H: usize>(maze: Maze<W, H>, args: &Args) {
+    let outcome = solve(&maze, args.solver, &Limits::default());
+    print(&outcome, args.solver, args.format);
+}
+
+fn main() -> ExitCode {
+    let args = match parse(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let bytes = match std::fs::read(&args.maze) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            eprintln!("error: failed to read '{}': {}", args.maze, e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match MazeFile::read(&bytes) {
+        Ok(MazeFile::Classic(maze)) => run(*maze, &args),
+        Ok(MazeFile::HalfSize(maze)) => run(*maze, &args),
+        Err(e) => {
+            eprintln!("error: failed to read maze '{}': {}", args.maze, e);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,314 @@
+use crate::maze::Relative;
+use crate::path::{Buffer, Path};
+use crate::vec::Vecu;
+
+/// A command for the motion controller of the mouse.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Drives forward through the amount of segments.
+    Forward(u16),
+    /// Turns 90 degrees to the left in place.
+    TurnLeft90,
+    /// Turns 90 degrees to the right in place.
+    TurnRight90,
+    /// Turns around in place.
+    Turn180,
+    /// Drives into the next segment while turning 90 degrees to the left,
+    /// ending in the same segment and heading as [Command::Forward] of 1 followed by
+    /// [Command::TurnLeft90].
+    SmoothLeft90,
+    /// Drives into the next segment while turning 90 degrees to the right,
+    /// ending in the same segment and heading as [Command::Forward] of 1 followed by
+    /// [Command::TurnRight90].
+    SmoothRight90,
+    /// Stops in the current segment.
+    Stop,
+}
+
+/// The commands of a [Path] through a maze of `W` by `H` segments. An optimized path never has
+/// more than two commands per segment, which fit in the buffer.
+pub type Commands<const W: usize, const H: usize> = Buffer<Command, W, H>;
+
+/// Returns the command that turns in place from `from` to `to`, or `None` if they are equal.
+fn turn(from: Relative, to: Relative) -> Option<Command> {
+    if from == to {
+        None
+    } else if from.left() == to {
+        Some(Command::TurnLeft90)
+    } else if from.right() == to {
+        Some(Command::TurnRight90)
+    } else {
+        Some(Command::Turn180)
+    }
+}
+
+/// Converts `path` into commands, where the mouse starts facing `heading`.
+///
+/// ### Description
+///
+/// The mouse turns in place whenever the path changes direction, and drives forward through
+/// every straight. The commands always end with [Command::Stop].
+///
+/// ### Panics
+///
+/// Panics if `path` has not been optimized, or if two consecutive segments of `path` are not
+/// neighbours.
+pub fn from_path<const W: usize, const H: usize>(
+    path: &Path<W, H>,
+    heading: Relative,
+) -> Commands<W, H> {
+    assert!(
+        path.optimized(),
+        "cannot convert an unoptimized path to commands"
+    );
+
+    let mut commands = Buffer::new(Command::Stop);
+    let mut heading = heading;
+    let mut forward = 0;
+
+    for i in 1..path.len() {
+        let (from, to) = (path.segment(i - 1).unwrap(), path.segment(i).unwrap());
+        let next = Relative::between(from, to).expect("Path segments must be neighbours");
+
+        if let Some(turn) = turn(heading, next) {
+            if forward > 0 {
+                commands.push(Command::Forward(forward));
+                forward = 0;
+            }
+            commands.push(turn);
+            heading = next;
+        }
+        forward += 1;
+    }
+
+    if forward > 0 {
+        commands.push(Command::Forward(forward));
+    }
+    commands.push(Command::Stop);
+    commands
+}
+
+/// Converts `path` into commands like [from_path], but turns smoothly where possible.
+///
+/// ### Description
+///
+/// Every 90 degree turn that follows a [Command::Forward] takes the last segment of it,
+/// and becomes a [Command::SmoothLeft90] or [Command::SmoothRight90]. Turns at the start
+/// and turning around are still done in place.
+///
+/// ### Panics
+///
+/// Panics if `path` has not been optimized, or if two consecutive segments of `path` are not
+/// neighbours.
+pub fn from_path_smooth<const W: usize, const H: usize>(
+    path: &Path<W, H>,
+    heading: Relative,
+) -> Commands<W, H> {
+    let mut commands = Buffer::new(Command::Stop);
+
+    for command in from_path(path, heading).iter().copied() {
+        let smooth = match command {
+            Command::TurnLeft90 => Command::SmoothLeft90,
+            Command::TurnRight90 => Command::SmoothRight90,
+            _ => {
+                commands.push(command);
+                continue;
+            }
+        };
+
+        match commands.last_mut() {
+            Some(Command::Forward(1)) => *commands.last_mut().unwrap() = smooth,
+            Some(Command::Forward(n)) => {
+                *n -= 1;
+                commands.push(smooth);
+            }
+            _ => commands.push(command),
+        }
+    }
+
+    commands
+}
+
+/// Returns the segment next to `pos` in `heading`, or `None` if it is outside a maze of `W` by
+/// `H` segments.
+fn step<const W: usize, const H: usize>(pos: Vecu, heading: Relative) -> Option<Vecu> {
+    let (x, y) = match heading {
+        Relative::North => (Some(pos.x), pos.y.checked_sub(1)),
+        Relative::East => (pos.x.checked_add(1), Some(pos.y)),
+        Relative::South => (Some(pos.x), pos.y.checked_add(1)),
+        Relative::West => (pos.x.checked_sub(1), Some(pos.y)),
+    };
+
+    match (x, y) {
+        (Some(x), Some(y)) if (x as usize) < W && (y as usize) < H => Some(Vecu { x, y }),
+        _ => None,
+    }
+}
+
+/// Converts `commands` back into the path that the mouse drives, starting at `start` facing
+/// `heading`. This is the reverse of [from_path] and [from_path_smooth].
+///
+/// ### Returns
+///
+/// The optimized path, or `None` if the mouse would leave the maze or drive through more
+/// segments than [Path::capacity]. Commands after [Command::Stop] are ignored.
+pub fn to_path<const W: usize, const H: usize>(
+    start: Vecu,
+    heading: Relative,
+    commands: &[Command],
+) -> Option<Path<W, H>> {
+    let mut path = Path::new();
+    let mut pos = start;
+    let mut heading = heading;
+    path.append(pos);
+
+    for command in commands {
+        let forward = match command {
+            Command::Forward(n) => *n,
+            Command::TurnLeft90 => {
+                heading = heading.left();
+                0
+            }
+            Command::TurnRight90 => {
+                heading = heading.right();
+                0
+            }
+            Command::Turn180 => {
+                heading = heading.opposite();
+                0
+            }
+            Command::SmoothLeft90 | Command::SmoothRight90 => 1,
+            Command::Stop => break,
+        };
+
+        for _ in 0..forward {
+            pos = step::<W, H>(pos, heading)?;
+            path.try_append(pos).ok()?;
+        }
+
+        match command {
+            Command::SmoothLeft90 => heading = heading.left(),
+            Command::SmoothRight90 => heading = heading.right(),
+            _ => {}
+        }
+    }
+
+    path.optimize();
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::Command::{
+        Forward, SmoothLeft90, SmoothRight90, Stop, Turn180, TurnLeft90, TurnRight90,
+    };
+    use crate::commands::{from_path, from_path_smooth, to_path};
+    use crate::maze::Relative;
+    use crate::path::{ClassicPath, Path};
+    use crate::vec::Vecu;
+
+    /// Returns an optimized path through `segments`.
+    fn path(segments: &[(u8, u8)]) -> ClassicPath {
+        let mut path = ClassicPath::new();
+        for (x, y) in segments {
+            path.append(Vecu { x: *x, y: *y });
+        }
+        path.optimize();
+        path
+    }
+
+    /// Returns the segments of `path`.
+    fn segments(path: &ClassicPath) -> Vec<Vecu> {
+        (0..path.len()).map(|i| path.segment(i).unwrap()).collect()
+    }
+
+    #[test]
+    fn commands() {
+        let path = path(&[(0, 0), (0, 1), (0, 2), (1, 2), (2, 2), (2, 1)]);
+
+        assert_eq!(
+            vec![
+                Forward(2),
+                TurnLeft90,
+                Forward(2),
+                TurnLeft90,
+                Forward(1),
+                Stop
+            ],
+            from_path(&path, Relative::South).as_slice()
+        );
+        assert_eq!(
+            vec![
+                TurnRight90,
+                Forward(2),
+                TurnLeft90,
+                Forward(2),
+                TurnLeft90,
+                Forward(1),
+                Stop
+            ],
+            from_path(&path, Relative::East).as_slice()
+        );
+        assert_eq!(
+            vec![
+                Turn180,
+                Forward(2),
+                TurnLeft90,
+                Forward(2),
+                TurnLeft90,
+                Forward(1),
+                Stop
+            ],
+            from_path(&path, Relative::North).as_slice()
+        );
+    }
+
+    #[test]
+    fn smooth() {
+        let path = path(&[(0, 0), (0, 1), (0, 2), (1, 2), (1, 3), (2, 3)]);
+
+        assert_eq!(
+            vec![
+                Forward(1),
+                SmoothLeft90,
+                SmoothRight90,
+                SmoothLeft90,
+                Forward(1),
+                Stop
+            ],
+            from_path_smooth(&path, Relative::South).as_slice()
+        );
+    }
+
+    #[test]
+    fn reverse() {
+        let path = path(&[
+            (0, 0),
+            (0, 1),
+            (0, 2),
+            (1, 2),
+            (1, 3),
+            (2, 3),
+            (2, 2),
+            (2, 1),
+        ]);
+
+        for heading in Relative::iter() {
+            let commands = from_path(&path, *heading);
+            let reversed: ClassicPath = to_path(Vecu::new(), *heading, &commands).unwrap();
+            assert_eq!(segments(&path), segments(&reversed));
+
+            let commands = from_path_smooth(&path, *heading);
+            let reversed: ClassicPath = to_path(Vecu::new(), *heading, &commands).unwrap();
+            assert_eq!(segments(&path), segments(&reversed));
+        }
+
+        let outside: Option<ClassicPath> = to_path(Vecu::new(), Relative::North, &[Forward(1)]);
+        assert!(outside.is_none());
+
+        // driving back and forth forever does not fit in the path
+        let commands = [Forward(1), Turn180].repeat(100);
+        let full: Option<Path<2, 2>> = to_path(Vecu::new(), Relative::South, &commands);
+        assert!(full.is_none());
+    }
+}
@@ -0,0 +1,387 @@
+use crate::physics::Wheels;
+
+/// The gains of a [Pid] controller.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Gains {
+    /// The proportional gain.
+    pub kp: f64,
+    /// The integral gain, per second.
+    pub ki: f64,
+    /// The derivative gain, in seconds.
+    pub kd: f64,
+}
+
+/// A proportional-integral-derivative controller.
+///
+/// ### Description
+///
+/// The output is clamped between a minimum and a maximum. While the output is clamped, the
+/// error is only integrated when it moves the output back into range, which prevents the
+/// integral from winding up while the actuator is saturated.
+///
+/// ### Implementation
+///
+/// The derivative is taken of the measurement instead of the error, such that a step in the
+/// setpoint does not cause a spike in the output. It is smoothed by a first-order low-pass filter
+/// with a configurable time constant, as differentiating a noisy measurement amplifies the noise.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Pid {
+    gains: Gains,
+    min: f64,
+    max: f64,
+    /// The time constant of the derivative filter in seconds.
+    filter: f64,
+    integral: f64,
+    derivative: f64,
+    previous: Option<f64>,
+}
+
+impl Pid {
+    /// Creates a new controller.
+    ///
+    /// ### Arguments
+    ///
+    /// - `gains` - The gains.
+    /// - `min` - The lowest output.
+    /// - `max` - The highest output.
+    /// - `filter` - The time constant of the derivative filter in seconds, or zero to not filter.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if `min` is larger than `max`, or if `filter` is negative.
+    pub fn new(gains: Gains, min: f64, max: f64, filter: f64) -> Self {
+        assert!(min <= max, "Minimum output must not exceed the maximum");
+        assert!(filter >= 0., "Filter time constant must not be negative");
+
+        Pid {
+            gains,
+            min,
+            max,
+            filter,
+            integral: 0.,
+            derivative: 0.,
+            previous: None,
+        }
+    }
+
+    /// Returns the gains.
+    pub fn gains(&self) -> Gains {
+        self.gains
+    }
+
+    /// Returns the integral of the error so far.
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+
+    /// Forgets the integral and the previous measurement.
+    pub fn reset(&mut self) {
+        self.integral = 0.;
+        self.derivative = 0.;
+        self.previous = None;
+    }
+
+    /// Returns the output for `measurement`, when `dt` seconds have passed since the previous
+    /// update.
+    ///
+    /// ### Arguments
+    ///
+    /// - `setpoint` - The desired value.
+    /// - `measurement` - The measured value.
+    /// - `dt` - The time in seconds since the previous update, which must be positive.
+    pub fn update(&mut self, setpoint: f64, measurement: f64, dt: f64) -> f64 {
+        let error = setpoint - measurement;
+
+        let raw = match self.previous {
+            Some(previous) => (previous - measurement) / dt,
+            None => 0.,
+        };
+        let smoothing = self.filter / (self.filter + dt);
+        self.derivative = smoothing * self.derivative + (1. - smoothing) * raw;
+        self.previous = Some(measurement);
+
+        let integral = self.integral + error * dt;
+        let gains = self.gains;
+        let output = gains.kp * error + gains.ki * integral + gains.kd * self.derivative;
+        let clamped = output.clamp(self.min, self.max);
+
+        // only integrate when the output is in range, or when the error unwinds it
+        if output == clamped
+            || (output > self.max && error < 0.)
+            || (output < self.min && error > 0.)
+        {
+            self.integral = integral;
+        }
+
+        clamped
+    }
+}
+
+/// The forward and angular velocity of the mouse.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Velocity {
+    /// The forward velocity in m/s.
+    pub forward: f64,
+    /// The angular velocity in rad/s, positive when turning to the right like
+    /// [crate::physics::Physics::angular_velocity].
+    pub angular: f64,
+}
+
+/// The gains of the controllers of a [DriveController].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tuning {
+    /// The gains of the speed controller of each wheel, from m/s to duty cycle.
+    pub wheel: Gains,
+    /// The gains of the forward velocity controller, from m/s to m/s.
+    pub forward: Gains,
+    /// The gains of the angular velocity controller, from rad/s to rad/s.
+    pub angular: Gains,
+    /// The time constant of the derivative filters in seconds.
+    pub filter: f64,
+}
+
+impl Default for Tuning {
+    /// Returns gains that suit the default [crate::physics::Robot], controlled at 1 kHz.
+    ///
+    /// The outer controllers are only proportional, as the wheel controllers already remove the
+    /// steady-state error, and an outer integral winds up while the motors are saturated.
+    fn default() -> Self {
+        Tuning {
+            wheel: Gains {
+                kp: 8.,
+                ki: 40.,
+                kd: 0.,
+            },
+            forward: Gains {
+                kp: 0.5,
+                ki: 0.,
+                kd: 0.,
+            },
+            angular: Gains {
+                kp: 0.5,
+                ki: 0.,
+                kd: 0.,
+            },
+            filter: 0.005,
+        }
+    }
+}
+
+/// The largest correction of the forward velocity controller in m/s.
+const MAX_FORWARD_CORRECTION_MS: f64 = 1.;
+/// The largest correction of the angular velocity controller in rad/s.
+const MAX_ANGULAR_CORRECTION_RADS: f64 = 10.;
+
+/// A cascaded controller that drives the mouse at a forward and angular velocity.
+///
+/// ### Description
+///
+/// The outer controllers compare the forward velocity measured by the wheel encoders and the
+/// angular velocity measured by the gyroscope with the target, and correct the target to make up
+/// for slip and differences between the motors. The corrected velocity is split into a speed for
+/// each wheel, and the inner controllers set the duty cycle of each motor to reach that speed.
+pub struct DriveController {
+    /// The distance between the wheels in m.
+    track: f64,
+    forward: Pid,
+    angular: Pid,
+    left: Pid,
+    right: Pid,
+    /// The distance the wheels had travelled at the previous update.
+    previous: Option<Wheels>,
+}
+
+impl DriveController {
+    /// Creates a new controller for a mouse with wheels `track` m apart.
+    pub fn new(track: f64, tuning: &Tuning) -> Self {
+        let wheel = Pid::new(tuning.wheel, -1., 1., tuning.filter);
+
+        DriveController {
+            track,
+            forward: Pid::new(
+                tuning.forward,
+                -MAX_FORWARD_CORRECTION_MS,
+                MAX_FORWARD_CORRECTION_MS,
+                tuning.filter,
+            ),
+            angular: Pid::new(
+                tuning.angular,
+                -MAX_ANGULAR_CORRECTION_RADS,
+                MAX_ANGULAR_CORRECTION_RADS,
+                tuning.filter,
+            ),
+            left: wheel,
+            right: wheel,
+            previous: None,
+        }
+    }
+
+    /// Forgets the state of every controller, such as after the mouse has been picked up.
+    pub fn reset(&mut self) {
+        self.forward.reset();
+        self.angular.reset();
+        self.left.reset();
+        self.right.reset();
+        self.previous = None;
+    }
+
+    /// Returns the duty cycle of both motors to reach `target`.
+    ///
+    /// ### Arguments
+    ///
+    /// - `target` - The desired velocity.
+    /// - `travelled` - The distance both wheels have travelled, as measured by
+    ///   [crate::hal::WheelEncoders::travelled].
+    /// - `angular_velocity` - The angular velocity measured by [crate::hal::Gyro].
+    /// - `dt` - The time in seconds since the previous update.
+    pub fn update(
+        &mut self,
+        target: Velocity,
+        travelled: Wheels,
+        angular_velocity: f64,
+        dt: f64,
+    ) -> Wheels {
+        let speeds = match self.previous {
+            Some(previous) => Wheels {
+                left: (travelled.left - previous.left) / dt,
+                right: (travelled.right - previous.right) / dt,
+            },
+            None => Wheels::default(),
+        };
+        self.previous = Some(travelled);
+
+        let forward = (speeds.left + speeds.right) / 2.;
+        let forward = target.forward + self.forward.update(target.forward, forward, dt);
+        let angular = target.angular + self.angular.update(target.angular, angular_velocity, dt);
+        let turn = angular * self.track / 2.;
+
+        Wheels {
+            left: self.left.update(forward + turn, speeds.left, dt),
+            right: self.right.update(forward - turn, speeds.right, dt),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::control::{DriveController, Gains, Pid, Tuning, Velocity};
+    use crate::hal::sim::SimulatedMouse;
+    use crate::hal::{DriveMotors, Gyro, WheelEncoders};
+    use crate::maze::Maze;
+    use crate::physics::{Physics, Robot};
+
+    const DT: f64 = 0.001;
+
+    #[test]
+    fn proportional() {
+        let gains = Gains {
+            kp: 2.,
+            ki: 0.,
+            kd: 0.,
+        };
+        let mut pid = Pid::new(gains, -1., 1., 0.);
+
+        assert_eq!(0.5, pid.update(1., 0.75, DT));
+        assert_eq!(1., pid.update(1., 0., DT));
+        assert_eq!(-1., pid.update(-1., 0., DT));
+    }
+
+    #[test]
+    fn anti_windup() {
+        let gains = Gains {
+            kp: 1.,
+            ki: 10.,
+            kd: 0.,
+        };
+        let mut pid = Pid::new(gains, -1., 1., 0.);
+
+        // the output is saturated for a long time, so the integral stops growing
+        for _ in 0..10_000 {
+            assert_eq!(1., pid.update(10., 0., DT));
+        }
+        assert!(pid.integral() <= 0.1 + 1e-9);
+
+        // the output leaves saturation as soon as the error changes sign
+        assert!(pid.update(0., 0.5, DT) < 1.);
+    }
+
+    #[test]
+    fn derivative() {
+        let gains = Gains {
+            kp: 0.,
+            ki: 0.,
+            kd: 1.,
+        };
+        let mut filtered = Pid::new(gains, -100., 100., 0.01);
+        let mut unfiltered = Pid::new(gains, -100., 100., 0.);
+
+        // a change of the setpoint does not kick the output
+        assert_eq!(0., unfiltered.update(0., 0., DT));
+        assert_eq!(0., unfiltered.update(1., 0., DT));
+
+        // a step in the measurement is smoothed by the filter
+        filtered.update(0., 0., DT);
+        assert_eq!(-100., unfiltered.update(0., 0.1, DT));
+        let first = filtered.update(0., 0.1, DT);
+        assert!(first < 0. && first > -10.);
+        assert!(filtered.update(0., 0.1, DT) > first);
+    }
+
+    /// Drives a simulated mouse at `target` for `seconds`, and returns the time in seconds after
+    /// which the velocity measured by `measure` stays within 2% of `setpoint`, and the highest
+    /// overshoot in the same unit.
+    fn step_response(
+        target: Velocity,
+        seconds: f64,
+        setpoint: f64,
+        measure: impl Fn(&SimulatedMouse<16, 16>) -> f64,
+    ) -> (f64, f64) {
+        let robot = Robot::default();
+        let mut mouse = SimulatedMouse::new(Physics::new(Maze::new(), robot, DT));
+        let mut controller = DriveController::new(robot.track, &Tuning::default());
+        let mut settled = 0.;
+        let mut overshoot: f64 = 0.;
+
+        for i in 1..=(seconds / DT) as usize {
+            let travelled = mouse.travelled().unwrap();
+            let angular = mouse.angular_velocity().unwrap();
+            let duty = controller.update(target, travelled, angular, DT);
+            mouse.set_duty(duty).unwrap();
+            mouse.step();
+
+            let value = measure(&mouse);
+            overshoot = overshoot.max((value - setpoint) * setpoint.signum());
+            if (value - setpoint).abs() > setpoint.abs() * 0.02 {
+                settled = i as f64 * DT;
+            }
+        }
+
+        (settled, overshoot)
+    }
+
+    #[test]
+    fn forward_step() {
+        let target = Velocity {
+            forward: 0.5,
+            angular: 0.,
+        };
+        let (settled, overshoot) =
+            step_response(target, 0.6, 0.5, |mouse| mouse.physics().velocity());
+
+        assert!(settled < 0.15, "settled after {} s", settled);
+        assert!(overshoot < 0.01, "overshot by {} m/s", overshoot);
+    }
+
+    #[test]
+    fn angular_step() {
+        let target = Velocity {
+            forward: 0.,
+            angular: 5.,
+        };
+        let (settled, overshoot) =
+            step_response(target, 0.6, 5., |mouse| mouse.physics().angular_velocity());
+
+        assert!(settled < 0.1, "settled after {} s", settled);
+        assert!(overshoot < 0.1, "overshot by {} rad/s", overshoot);
+    }
+}
@@ -0,0 +1,336 @@
+use crate::floodfill::UNREACHABLE;
+use crate::grid::Grid;
+use crate::heap::Heap;
+use crate::maze::{Maze, Relative, Wall};
+use crate::vec::Vecu;
+
+/// The priority of a segment in the queue of a [DStarLite] planner.
+type Key = (u32, u32);
+
+/// An incremental planner that maintains the distance from the mouse to the goal
+/// using D* Lite.
+///
+/// ### Description
+///
+/// Like [crate::floodfill::flood], the planner assumes that unknown walls are open.
+/// Unlike a full reflood, discovering a wall through [DStarLite::update_walls] only updates the
+/// segments whose distance is affected by the wall and that are relevant to the mouse,
+/// which makes replanning during exploration much cheaper.
+///
+/// The planner only maintains the distance of the segment the mouse is in, and the distances
+/// needed to find the next move from it. The distances stored in the [Maze] itself are not
+/// updated by the planner.
+///
+/// ### Implementation
+///
+/// Distances are computed backwards from the goal. Every segment has a distance `g` and a
+/// one-step lookahead `rhs`, which is the smallest distance of any neighbour plus 1. Segments
+/// where these differ are inconsistent and are kept in a priority queue, ordered by their
+/// distance plus the Manhattan distance to the mouse. Inconsistent segments are processed
+/// until the segment of the mouse is consistent and no segment in the queue could lower its
+/// distance.
+///
+/// When a wall is found, only the segments on both sides of it get a new `rhs`. As the mouse
+/// moves, the priorities in the queue are kept valid by adding the distance moved since the
+/// last replan to every new key, instead of recomputing the entire queue.
+pub struct DStarLite<const W: usize, const H: usize> {
+    g: Grid<u16, W, H>,
+    rhs: Grid<u16, W, H>,
+    queue: Heap<Key, 1, W, H>,
+    start: Vecu,
+    last: Vecu,
+    km: u32,
+}
+
+/// Returns the Manhattan distance between `a` and `b`.
+fn heuristic(a: Vecu, b: Vecu) -> u32 {
+    (a.x.abs_diff(b.x) + a.y.abs_diff(b.y)) as u32
+}
+
+impl<const W: usize, const H: usize> DStarLite<W, H> {
+    /// Creates a new planner for `maze` with the mouse at `start`,
+    /// and computes the distance from `start` to the goal.
+    pub fn new(maze: &Maze<W, H>, start: Vecu) -> Self {
+        let mut planner = DStarLite {
+            g: Grid::new(UNREACHABLE),
+            rhs: Grid::new(UNREACHABLE),
+            queue: Heap::new(),
+            start,
+            last: start,
+            km: 0,
+        };
+
+        for pos in maze.goal().iter() {
+            planner.rhs[pos] = 0;
+            let key = planner.key(pos);
+            planner.queue.push(planner.node(pos), key);
+        }
+
+        planner.compute(maze);
+        planner
+    }
+
+    /// Returns the position of the mouse.
+    pub fn start(&self) -> Vecu {
+        self.start
+    }
+
+    /// Returns the length of the shortest route from the mouse to the goal,
+    /// or [UNREACHABLE] if the goal cannot be reached.
+    pub fn distance(&self) -> u16 {
+        // the lookahead of the mouse is always up to date, unlike its distance
+        self.rhs(self.start)
+    }
+
+    /// Returns the neighbour of the mouse that is on a shortest route to the goal.
+    /// Returns `None` if the mouse is in the goal, or if the goal cannot be reached.
+    pub fn next(&self, maze: &Maze<W, H>) -> Option<Vecu> {
+        if maze.goal().contains(self.start) {
+            return None;
+        }
+
+        let mut best: Option<(Vecu, u16)> = None;
+        for pos in Self::neighbours(maze, self.start).into_iter().flatten() {
+            let g = self.g(pos);
+            if g != UNREACHABLE && best.is_none_or(|(_, distance)| g < distance) {
+                best = Some((pos, g));
+            }
+        }
+
+        best.map(|(pos, _)| pos)
+    }
+
+    /// Moves the mouse to `pos`.
+    pub fn move_to(&mut self, pos: Vecu) {
+        self.start = pos;
+    }
+
+    /// Updates the walls of the segment at `x, y` in `maze` like [Maze::update_walls],
+    /// and replans for every wall that is found to be present.
+    ///
+    /// The distances stored in `maze` are not recomputed.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if a wall that is already known is observed to be different.
+    pub fn update_walls<T: Into<Wall>>(
+        &mut self,
+        maze: &mut Maze<W, H>,
+        x: u8,
+        y: u8,
+        walls: [T; 4],
+    ) {
+        let pos = Vecu { x, y };
+        let before = maze.segment(x, y).walls;
+        if !maze.set_walls(x, y, walls) {
+            return;
+        }
+
+        self.km += heuristic(self.last, self.start);
+        self.last = self.start;
+
+        let after = maze.segment(x, y).walls;
+        for (i, dir) in Relative::iter().enumerate() {
+            if before[i].is_present() || !after[i].is_present() {
+                continue;
+            }
+
+            let relative = match maze.segment(x, y).relative(maze, dir) {
+                Some(relative) => relative.pos(),
+                None => continue,
+            };
+
+            // the cost of the edge between both sides increased from 1 to infinity
+            for (from, to) in [(pos, relative), (relative, pos)] {
+                if self.rhs(from) == self.g(to).saturating_add(1) && !maze.goal().contains(from) {
+                    let rhs = self.lookahead(maze, from);
+                    self.rhs[from] = rhs;
+                }
+                self.update(from);
+            }
+        }
+
+        self.compute(maze);
+    }
+
+    /// Returns the index of `pos` in the queue.
+    fn node(&self, pos: Vecu) -> usize {
+        pos.x as usize + pos.y as usize * W
+    }
+
+    /// Returns the position of the queue index `node`.
+    fn pos(&self, node: usize) -> Vecu {
+        Vecu {
+            x: (node % W) as u8,
+            y: (node / W) as u8,
+        }
+    }
+
+    fn g(&self, pos: Vecu) -> u16 {
+        self.g[pos]
+    }
+
+    fn rhs(&self, pos: Vecu) -> u16 {
+        self.rhs[pos]
+    }
+
+    /// Returns the priority of `pos` in the queue.
+    fn key(&self, pos: Vecu) -> Key {
+        let distance = self.g(pos).min(self.rhs(pos));
+        if distance == UNREACHABLE {
+            return (u32::MAX, u32::MAX);
+        }
+
+        let distance = distance as u32;
+        (distance + heuristic(self.start, pos) + self.km, distance)
+    }
+
+    /// Returns the neighbours of `pos` that are not separated from it by a present wall.
+    fn neighbours(maze: &Maze<W, H>, pos: Vecu) -> [Option<Vecu>; 4] {
+        let segment = maze.segment_vec(pos);
+        let mut neighbours = [None; 4];
+
+        for (i, dir) in Relative::iter().enumerate() {
+            if !segment.walls[i].is_present() {
+                neighbours[i] = segment.relative(maze, dir).map(|relative| relative.pos());
+            }
+        }
+
+        neighbours
+    }
+
+    /// Returns the smallest distance of any neighbour of `pos`, plus 1.
+    fn lookahead(&self, maze: &Maze<W, H>, pos: Vecu) -> u16 {
+        Self::neighbours(maze, pos)
+            .into_iter()
+            .flatten()
+            .map(|neighbour| self.g(neighbour).saturating_add(1))
+            .min()
+            .unwrap_or(UNREACHABLE)
+    }
+
+    /// Adds `pos` to the queue if it is inconsistent, or removes it otherwise.
+    fn update(&mut self, pos: Vecu) {
+        let node = self.node(pos);
+        if self.g(pos) != self.rhs(pos) {
+            let key = self.key(pos);
+            self.queue.push(node, key);
+        } else {
+            self.queue.remove(node);
+        }
+    }
+
+    /// Processes inconsistent segments until the distance of the mouse is correct.
+    fn compute(&mut self, maze: &Maze<W, H>) {
+        while let Some((node, old)) = self.queue.peek() {
+            let start = self.start;
+            if old >= self.key(start) && self.rhs(start) <= self.g(start) {
+                break;
+            }
+
+            let pos = self.pos(node);
+            let new = self.key(pos);
+            let (g, rhs) = (self.g(pos), self.rhs(pos));
+
+            if old < new {
+                self.queue.push(node, new);
+            } else if g > rhs {
+                // overconsistent, so the distance of `pos` is final
+                self.g[pos] = rhs;
+                self.queue.remove(node);
+
+                for neighbour in Self::neighbours(maze, pos).into_iter().flatten() {
+                    if rhs.saturating_add(1) < self.rhs(neighbour) {
+                        self.rhs[neighbour] = rhs + 1;
+                        self.update(neighbour);
+                    }
+                }
+            } else {
+                // underconsistent, so every segment that relied on `pos` must be recomputed
+                self.g[pos] = UNREACHABLE;
+
+                let neighbours = Self::neighbours(maze, pos);
+                for affected in neighbours.into_iter().flatten().chain([pos]) {
+                    if !maze.goal().contains(affected)
+                        && (affected == pos || self.rhs(affected) == g.saturating_add(1))
+                    {
+                        let rhs = self.lookahead(maze, affected);
+                        self.rhs[affected] = rhs;
+                    }
+                    self.update(affected);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dstar::DStarLite;
+    use crate::floodfill::UNREACHABLE;
+    use crate::goal::Goal;
+    use crate::maze::generate::{generate, Algorithm};
+    use crate::maze::{Assumption, Maze};
+    use crate::vec::Vecu;
+
+    /// Returns the length of the shortest route in `maze` from `pos`, like a full reflood.
+    fn bfs<const W: usize, const H: usize>(maze: &Maze<W, H>, pos: Vecu) -> u16 {
+        maze.shortest_route(pos, Assumption::Optimistic)
+            .unwrap_or(UNREACHABLE)
+    }
+
+    #[test]
+    fn known_maze() {
+        for seed in 0..3 {
+            let maze: Maze<16, 16> = generate(Algorithm::Backtracker, seed, 0.2);
+
+            for x in 0..16 {
+                for y in 0..16 {
+                    let pos = Vecu { x, y };
+                    let planner = DStarLite::new(&maze, pos);
+                    assert_eq!(bfs(&maze, pos), planner.distance());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn explore() {
+        for (seed, algorithm) in [Algorithm::Backtracker, Algorithm::Prim, Algorithm::Kruskal]
+            .into_iter()
+            .enumerate()
+        {
+            let real: Maze<16, 16> = generate(algorithm, seed as u64, 0.1);
+            let mut maze: Maze<16, 16> = Maze::new();
+            let mut planner = DStarLite::new(&maze, Vecu::new());
+
+            let mut moves = 0;
+            while !maze.goal().contains(planner.start()) {
+                let pos = planner.start();
+                let walls = real.segment(pos.x, pos.y).walls;
+                planner.update_walls(&mut maze, pos.x, pos.y, walls);
+                assert_eq!(bfs(&maze, pos), planner.distance());
+
+                let next = planner.next(&maze).unwrap();
+                assert_eq!(planner.distance() - 1, bfs(&maze, next));
+                planner.move_to(next);
+
+                moves += 1;
+                assert!(moves < 16 * 16 * 4, "Exploration did not reach the goal");
+            }
+
+            assert_eq!(0, planner.distance());
+        }
+    }
+
+    #[test]
+    fn unreachable() {
+        let mut maze: Maze<3, 3> = Maze::with_goal(Goal::cell(Vecu { x: 2, y: 2 }));
+        let mut planner = DStarLite::new(&maze, Vecu::new());
+        assert_eq!(4, planner.distance());
+
+        planner.update_walls(&mut maze, 2, 2, [true, true, true, true]);
+        assert_eq!(UNREACHABLE, planner.distance());
+        assert_eq!(None, planner.next(&maze));
+    }
+}
@@ -0,0 +1,42 @@
+use crate::maze::Relative;
+use crate::vec::Vecu;
+
+/// An error that occurred while updating a maze or a path, from which the robot can recover.
+///
+/// ### Description
+///
+/// Returned by the `try_` variants of functions that panic otherwise, such as
+/// [crate::maze::Maze::try_update_walls] and [crate::pathfinder::try_next].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The position is outside the maze.
+    OutOfBounds { pos: Vecu },
+    /// The wall on side `relative` of the segment at `pos` is already known, and was observed
+    /// to be different.
+    ConflictingWall { pos: Vecu, relative: Relative },
+    /// The path has no segments.
+    EmptyPath,
+    /// The path cannot hold any more segments.
+    PathFull,
+    /// The path has not been optimized, while an optimized path is required.
+    UnoptimizedPath,
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::OutOfBounds { pos } => write!(f, "{:?} is outside the maze", pos),
+            Error::ConflictingWall { pos, relative } => write!(
+                f,
+                "the {:?} wall of {:?} is known, and cannot be changed",
+                relative, pos
+            ),
+            Error::EmptyPath => write!(f, "the path is empty"),
+            Error::PathFull => write!(f, "the path is full"),
+            Error::UnoptimizedPath => write!(f, "the path must be optimized"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
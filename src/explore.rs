@@ -0,0 +1,232 @@
+use crate::floodfill::{distances, UNREACHABLE};
+use crate::grid::BitGrid;
+use crate::maze::{Assumption, Maze, Relative, Wall};
+use crate::vec::Vecu;
+
+/// The result of an exploration using [explore].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Stats {
+    /// Whether the shortest route from the start to the goal is proven to be optimal.
+    pub proven: bool,
+    /// The amount of distinct segments the mouse has been in, including the start.
+    pub visited: u16,
+    /// The amount of segments the mouse has moved.
+    pub moves: u32,
+    /// The position of the mouse at the end of the exploration.
+    pub position: Vecu,
+}
+
+/// Returns the segment that may still shorten the best route from `start` to the goal and that
+/// is nearest to `pos`, or `None` if no such segment is left to visit.
+///
+/// ### Description
+///
+/// A segment may shorten the best route if it has unknown walls and lies on a shortest route
+/// from `start` to the goal when all unknown walls are assumed to be open. If no such segment
+/// exists, every optimistic shortest route only uses known segments, and so only uses walls that
+/// are known to be open.
+///
+/// Segments in `visited` are skipped, as visiting them again does not reveal the walls that the
+/// sensors could not observe from them.
+fn target<const W: usize, const H: usize>(
+    maze: &Maze<W, H>,
+    visited: &BitGrid<W, H>,
+    start: Vecu,
+    pos: Vecu,
+) -> Option<Vecu> {
+    let best = maze.segment_vec(start).distance;
+    if best == UNREACHABLE {
+        return None;
+    }
+
+    let from_start = distances(maze, [start], Assumption::Optimistic);
+    let from_pos = distances(maze, [pos], Assumption::Optimistic);
+    let mut target: Option<(Vecu, u16)> = None;
+
+    for x in 0..W as u8 {
+        for y in 0..H as u8 {
+            let pos = Vecu { x, y };
+            let to_start = from_start[pos];
+            let to_pos = from_pos[pos];
+            if maze.is_known(x, y)
+                || visited.get(pos)
+                || to_start == UNREACHABLE
+                || to_pos == UNREACHABLE
+                || to_start + maze.segment(x, y).distance != best
+            {
+                continue;
+            }
+
+            if target.is_none_or(|(_, distance)| to_pos < distance) {
+                target = Some((pos, to_pos));
+            }
+        }
+    }
+
+    target.map(|(pos, _)| pos)
+}
+
+/// Explores `maze` until the shortest route from `start` to the goal is proven to be optimal.
+///
+/// ### Description
+///
+/// The mouse starts at `start` and senses the walls of every segment it enters using `sense`,
+/// which returns the observed walls of a segment in the same order as [crate::maze::Segment]
+/// walls. Walls that are not observed may be [Wall::Unknown].
+///
+/// Instead of stopping in the goal, the mouse keeps visiting segments whose unknown walls could
+/// still make the route shorter. The route is proven when the shortest route over walls known to
+/// be open is as short as the shortest route when all unknown walls are assumed to be open.
+///
+/// ### Implementation
+///
+/// After every move, the segment to visit next is chosen by [target]: the nearest unvisited
+/// segment with unknown walls on any optimistic shortest route from `start` to the goal. The
+/// mouse then takes one step along an optimistic shortest route to that segment, and senses its
+/// new position. The exploration ends when no such segment exists, or the goal cannot be reached.
+/// When `sense` leaves walls unknown, the route may not be proven at the end.
+///
+/// ### Arguments
+///
+/// - `maze` - The known maze, which is updated with every observation.
+/// - `start` - The position of the mouse, which is also the start of the route.
+/// - `sense` - Returns the walls observed from the segment at the provided position.
+pub fn explore<const W: usize, const H: usize, F>(
+    maze: &mut Maze<W, H>,
+    start: Vecu,
+    mut sense: F,
+) -> Stats
+where
+    F: FnMut(Vecu) -> [Wall; 4],
+{
+    let mut visited: BitGrid<W, H> = BitGrid::new();
+    let mut stats = Stats {
+        proven: false,
+        visited: 1,
+        moves: 0,
+        position: start,
+    };
+
+    visited.set(start, true);
+    maze.update_walls(start.x, start.y, sense(start));
+
+    while let Some(goal) = target(maze, &visited, start, stats.position) {
+        let to_goal = distances(maze, [goal], Assumption::Optimistic);
+        let current = maze.segment_vec(stats.position);
+        let distance = |pos: Vecu| to_goal[pos];
+
+        let next = Relative::iter()
+            .enumerate()
+            .filter(|(i, _)| current.walls[*i].is_passable(Assumption::Optimistic))
+            .filter_map(|(_, dir)| current.relative(maze, dir))
+            .map(|segment| segment.pos())
+            .find(|pos| distance(*pos) < distance(stats.position))
+            .expect("Failed to find a step towards the target");
+
+        stats.position = next;
+        stats.moves += 1;
+        if !visited.set(next, true) {
+            stats.visited += 1;
+        }
+
+        maze.update_walls(next.x, next.y, sense(next));
+    }
+
+    stats.proven = maze.is_route_optimal(start);
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::explore::explore;
+    use crate::goal::Goal;
+    use crate::maze::generate::{generate, Algorithm};
+    use crate::maze::{Assumption, Maze, Relative, Wall};
+    use crate::vec::Vecu;
+
+    #[test]
+    fn proves_optimal() {
+        let algorithms = [Algorithm::Backtracker, Algorithm::Prim, Algorithm::Kruskal];
+
+        for (seed, algorithm) in algorithms.into_iter().enumerate() {
+            for loopiness in [0., 0.3] {
+                let real: Maze<16, 16> = generate(algorithm, seed as u64, loopiness);
+                let mut maze: Maze<16, 16> = Maze::new();
+
+                let stats = explore(&mut maze, Vecu::new(), |pos| {
+                    real.segment(pos.x, pos.y).walls
+                });
+
+                assert!(stats.proven);
+                assert!(stats.visited as usize <= 16 * 16);
+                assert!(stats.moves >= stats.visited as u32 - 1);
+                assert_eq!(
+                    real.shortest_route(Vecu::new(), Assumption::Pessimistic),
+                    maze.shortest_route(Vecu::new(), Assumption::Pessimistic)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn partial_observations() {
+        // the north wall is never observed
+        let real: Maze<16, 16> = generate(Algorithm::Prim, 1, 0.1);
+        let mut maze: Maze<16, 16> = Maze::new();
+        let stats = explore(&mut maze, Vecu::new(), |pos| {
+            let mut walls = real.segment(pos.x, pos.y).walls;
+            walls[Relative::North as usize] = Wall::Unknown;
+            walls
+        });
+        assert!(stats.visited as usize <= 16 * 16);
+
+        // only the walls in front of and beside the mouse are observed
+        for seed in 0..5 {
+            let real: Maze<16, 16> = generate(Algorithm::Backtracker, seed, 0.2);
+            let mut maze: Maze<16, 16> = Maze::new();
+            let mut previous = Vecu::new();
+
+            let stats = explore(&mut maze, Vecu::new(), |pos| {
+                let heading = Relative::between(previous, pos).unwrap_or(Relative::South);
+                previous = pos;
+
+                let walls = real.segment(pos.x, pos.y).walls;
+                let mut observed = [Wall::Unknown; 4];
+                for dir in [heading, heading.left(), heading.right()] {
+                    observed[dir as usize] = walls[dir as usize];
+                }
+                observed
+            });
+
+            assert!(stats.visited as usize <= 16 * 16);
+            if stats.proven {
+                assert_eq!(
+                    real.shortest_route(Vecu::new(), Assumption::Pessimistic),
+                    maze.shortest_route(Vecu::new(), Assumption::Pessimistic)
+                );
+            }
+        }
+    }
+
+    const WALLED_IN: &str = "\
++---+---+---+
+|       |   |
++   +   +---+
+|           |
++   +   +   +
+|           |
++---+---+---+
+";
+
+    #[test]
+    fn unreachable() {
+        let real: Maze<3, 3> = Maze::from_ascii(WALLED_IN).unwrap();
+        let mut maze: Maze<3, 3> = Maze::with_goal(Goal::cell(Vecu { x: 2, y: 2 }));
+        let stats = explore(&mut maze, Vecu::new(), |pos| {
+            real.segment(pos.x, pos.y).walls
+        });
+
+        assert!(!stats.proven);
+        assert!(stats.visited < 9);
+    }
+}
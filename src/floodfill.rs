@@ -0,0 +1,186 @@
+use crate::grid::Grid;
+use crate::maze::{Assumption, Maze, Relative};
+use crate::vec::Vecu;
+
+/// The distance of a segment from which the goal cannot be reached.
+pub const UNREACHABLE: u16 = u16::MAX;
+
+/// A first-in, first-out queue of positions with room for every segment of a maze of
+/// `W` by `H` segments, without any allocation.
+pub(crate) struct Queue<const W: usize, const H: usize> {
+    buffer: [[Vecu; W]; H],
+    head: usize,
+    len: usize,
+}
+
+impl<const W: usize, const H: usize> Queue<W, H> {
+    /// Returns a new empty queue.
+    pub(crate) fn new() -> Self {
+        Queue {
+            buffer: [[Vecu::new(); W]; H],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Appends `pos` to the back of the queue.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the queue already holds `W * H` positions.
+    pub(crate) fn push_back(&mut self, pos: Vecu) {
+        assert!(self.len < W * H, "Queue is full");
+
+        let buffer = self.buffer.as_flattened_mut();
+        buffer[(self.head + self.len) % (W * H)] = pos;
+        self.len += 1;
+    }
+
+    /// Removes and returns the position at the front of the queue.
+    pub(crate) fn pop_front(&mut self) -> Option<Vecu> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let pos = self.buffer.as_flattened()[self.head];
+        self.head = (self.head + 1) % (W * H);
+        self.len -= 1;
+        Some(pos)
+    }
+}
+
+/// Returns the length of the shortest route from every segment in `maze` to the nearest
+/// segment in `sources`, or [UNREACHABLE] if none can be reached.
+///
+/// ### Description
+///
+/// Unknown walls are treated according to `assumption`.
+///
+/// ### Implementation
+///
+/// Every segment in `sources` gets a distance of zero and is added to a queue. Then,
+/// breadth-first search is performed: the front of the queue is removed, and every neighbour
+/// that is not separated from it by an impassable wall and has not been reached yet gets the
+/// distance of the front plus 1 and is added to the back of the queue. Every segment is added at
+/// most once, so the queue never holds more than `W * H` positions.
+///
+/// ### Arguments
+///
+/// - `maze` - The maze.
+/// - `sources` - The segments to compute the distance to.
+/// - `assumption` - How to treat unknown walls.
+pub fn distances<const W: usize, const H: usize>(
+    maze: &Maze<W, H>,
+    sources: impl IntoIterator<Item = Vecu>,
+    assumption: Assumption,
+) -> Grid<u16, W, H> {
+    let mut distances = Grid::new(UNREACHABLE);
+    let mut queue: Queue<W, H> = Queue::new();
+
+    for pos in sources {
+        if distances[pos] == UNREACHABLE {
+            distances[pos] = 0;
+            queue.push_back(pos);
+        }
+    }
+
+    while let Some(pos) = queue.pop_front() {
+        let current = maze.segment_vec(pos);
+        let distance = distances[pos];
+
+        for (i, dir) in Relative::iter().enumerate() {
+            if !current.walls[i].is_passable(assumption) {
+                continue;
+            }
+
+            let next = match current.relative(maze, dir) {
+                Some(relative) => relative.pos(),
+                None => continue,
+            };
+
+            if distances[next] == UNREACHABLE {
+                distances[next] = distance + 1;
+                queue.push_back(next);
+            }
+        }
+    }
+
+    distances
+}
+
+/// Recomputes the distance of every segment in `maze` to its goal.
+///
+/// ### Description
+///
+/// After flooding, the distance of every segment is the length of the shortest route to the
+/// nearest segment of the goal, assuming that unknown walls are open. Segments from which the
+/// goal cannot be reached get a distance of [UNREACHABLE].
+///
+/// ### Arguments
+///
+/// - `maze` - The maze to update the distances of.
+pub fn flood<const W: usize, const H: usize>(maze: &mut Maze<W, H>) {
+    let distances = distances(maze, maze.goal().iter(), Assumption::Optimistic);
+
+    for (pos, distance) in distances.iter() {
+        maze.update_distance(pos.x, pos.y, distance);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::floodfill::{flood, Queue, UNREACHABLE};
+    use crate::goal::Goal;
+    use crate::maze::generate::{generate, Algorithm};
+    use crate::maze::{Assumption, Maze};
+    use crate::vec::Vecu;
+
+    #[test]
+    fn queue() {
+        let mut queue: Queue<2, 1> = Queue::new();
+
+        queue.push_back(Vecu { x: 0, y: 0 });
+        queue.push_back(Vecu { x: 1, y: 0 });
+        assert_eq!(Some(Vecu { x: 0, y: 0 }), queue.pop_front());
+
+        queue.push_back(Vecu { x: 2, y: 0 });
+        assert_eq!(Some(Vecu { x: 1, y: 0 }), queue.pop_front());
+        assert_eq!(Some(Vecu { x: 2, y: 0 }), queue.pop_front());
+        assert_eq!(None, queue.pop_front());
+    }
+
+    #[test]
+    fn matches_shortest_route() {
+        for seed in 0..5 {
+            let mut maze: Maze<16, 16> = generate(Algorithm::Kruskal, seed, 0.1);
+            flood(&mut maze);
+
+            for x in 0..16 {
+                for y in 0..16 {
+                    let route = maze.shortest_route(Vecu { x, y }, Assumption::Optimistic);
+                    assert_eq!(route, Some(maze.segment(x, y).distance));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn update_walls() {
+        let mut maze: Maze<5, 5> = Maze::with_goal(Goal::cell(Vecu { x: 4, y: 0 }));
+        assert_eq!(4, maze.segment(0, 0).distance);
+
+        // # # # #
+        // #     |
+        maze.update_walls(3, 0, [None, Some(true), None, None]);
+        assert_eq!(6, maze.segment(0, 0).distance);
+        assert_eq!(3, maze.segment(3, 0).distance);
+
+        // learning about open walls does not change any distance
+        maze.update_walls(2, 0, [true, false, false, false]);
+        assert_eq!(6, maze.segment(0, 0).distance);
+
+        // wall in the goal entirely
+        maze.update_walls(4, 1, [true, true, true, true]);
+        assert_eq!(UNREACHABLE, maze.segment(0, 0).distance);
+    }
+}
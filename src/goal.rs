@@ -0,0 +1,196 @@
+use crate::vec::Vecu;
+
+/// Returns the lowest and highest center coordinate of an axis with `len` segments.
+/// Both are equal when `len` is odd.
+fn center(len: usize) -> (u8, u8) {
+    let half = (len / 2) as u8;
+    if len.is_multiple_of(2) {
+        (half - 1, half)
+    } else {
+        (half, half)
+    }
+}
+
+/// Represents the set of segments that the pathfinding is trying to reach
+/// in a maze of `W` by `H` segments.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Goal<const W: usize, const H: usize> {
+    cells: [[bool; W]; H],
+}
+
+impl<const W: usize, const H: usize> Goal<W, H> {
+    /// Creates a new goal without any segments.
+    pub fn empty() -> Self {
+        Goal {
+            cells: [[false; W]; H],
+        }
+    }
+
+    /// Creates a new goal consisting of the single segment at `pos`.
+    pub fn cell(pos: Vecu) -> Self {
+        Self::cells(&[pos])
+    }
+
+    /// Creates a new goal consisting of `cells`.
+    pub fn cells(cells: &[Vecu]) -> Self {
+        let mut goal = Self::empty();
+        for pos in cells {
+            goal.insert(*pos);
+        }
+        goal
+    }
+
+    /// Creates a new goal consisting of all segments in the rectangle from `min` to `max`,
+    /// both inclusive.
+    pub fn rect(min: Vecu, max: Vecu) -> Self {
+        let mut goal = Self::empty();
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                goal.insert(Vecu { x, y });
+            }
+        }
+        goal
+    }
+
+    /// Creates a new goal consisting of the center of the maze.
+    /// This is a 2x2 square for even dimensions, and narrows to a single row or column of
+    /// segments for odd dimensions.
+    pub fn center() -> Self {
+        let (low_x, high_x) = center(W);
+        let (low_y, high_y) = center(H);
+
+        Self::rect(
+            Vecu { x: low_x, y: low_y },
+            Vecu {
+                x: high_x,
+                y: high_y,
+            },
+        )
+    }
+
+    /// Creates a new goal consisting of a `size` by `size` square in the center of the maze,
+    /// such as the 4x4 center of some half-size mazes.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if `size` is zero, or larger than the width or height of the maze.
+    pub fn centered(size: u8) -> Self {
+        assert!(size > 0, "Goal size must be at least 1");
+        assert!(
+            size as usize <= W && size as usize <= H,
+            "Goal of {size}x{size} does not fit in a {W}x{H} maze"
+        );
+
+        let min = Vecu {
+            x: (W as u8 - size) / 2,
+            y: (H as u8 - size) / 2,
+        };
+        let max = Vecu {
+            x: min.x + size - 1,
+            y: min.y + size - 1,
+        };
+
+        Self::rect(min, max)
+    }
+
+    /// Creates a new goal consisting of the origin, where the mouse starts.
+    pub fn origin() -> Self {
+        Self::cell(Vecu::new())
+    }
+
+    /// Adds the segment at `pos` to this goal.
+    pub fn insert(&mut self, pos: Vecu) {
+        self.cells[pos.y as usize][pos.x as usize] = true;
+    }
+
+    /// Whether the segment at `pos` is part of this goal.
+    pub fn contains(&self, pos: Vecu) -> bool {
+        self.cells[pos.y as usize][pos.x as usize]
+    }
+
+    /// Whether this goal has no segments.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Returns an iterator over the positions of all segments in this goal.
+    pub fn iter(&self) -> impl Iterator<Item = Vecu> + '_ {
+        (0..H as u8)
+            .flat_map(|y| (0..W as u8).map(move |x| Vecu { x, y }))
+            .filter(|pos| self.contains(*pos))
+    }
+}
+
+impl<const W: usize, const H: usize> Default for Goal<W, H> {
+    fn default() -> Self {
+        Self::center()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::goal::Goal;
+    use crate::vec::Vecu;
+
+    #[test]
+    fn center() {
+        let goal: Goal<16, 16> = Goal::center();
+        let cells: Vec<Vecu> = goal.iter().collect();
+
+        assert_eq!(
+            vec![
+                Vecu { x: 7, y: 7 },
+                Vecu { x: 8, y: 7 },
+                Vecu { x: 7, y: 8 },
+                Vecu { x: 8, y: 8 }
+            ],
+            cells
+        );
+
+        let goal: Goal<5, 4> = Goal::center();
+        assert_eq!(2, goal.iter().count());
+        assert!(goal.contains(Vecu { x: 2, y: 1 }));
+        assert!(goal.contains(Vecu { x: 2, y: 2 }));
+    }
+
+    #[test]
+    fn centered() {
+        let goal: Goal<32, 32> = Goal::centered(4);
+
+        assert_eq!(16, goal.iter().count());
+        assert!(goal.contains(Vecu { x: 14, y: 14 }));
+        assert!(goal.contains(Vecu { x: 17, y: 17 }));
+        assert!(!goal.contains(Vecu { x: 18, y: 17 }));
+    }
+
+    #[test]
+    fn centered_fits() {
+        let goal: Goal<5, 3> = Goal::centered(3);
+
+        assert_eq!(9, goal.iter().count());
+        assert!(goal.contains(Vecu { x: 1, y: 0 }));
+        assert!(goal.contains(Vecu { x: 3, y: 2 }));
+    }
+
+    #[test]
+    #[should_panic(expected = "Goal size must be at least 1")]
+    fn centered_empty() {
+        Goal::<16, 16>::centered(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Goal of 4x4 does not fit in a 5x3 maze")]
+    fn centered_too_large() {
+        Goal::<5, 3>::centered(4);
+    }
+
+    #[test]
+    fn cells() {
+        let goal: Goal<5, 5> = Goal::cells(&[Vecu { x: 4, y: 0 }, Vecu { x: 0, y: 4 }]);
+
+        assert!(goal.contains(Vecu { x: 4, y: 0 }));
+        assert!(goal.contains(Vecu { x: 0, y: 4 }));
+        assert!(!goal.contains(Vecu { x: 2, y: 2 }));
+        assert!(Goal::<5, 5>::empty().is_empty());
+    }
+}
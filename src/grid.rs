@@ -0,0 +1,206 @@
+use crate::maze::xy_to_index;
+use crate::vec::Vecu;
+
+/// Returns the index of `pos` in the cells of a grid of `W` by `H` segments.
+fn index<const W: usize, const H: usize>(pos: Vecu) -> usize {
+    debug_assert!(
+        (pos.x as usize) < W && (pos.y as usize) < H,
+        "Position is outside the grid"
+    );
+    xy_to_index::<W>(pos.x, pos.y)
+}
+
+/// Returns the positions of a grid of `W` by `H` segments, row by row.
+fn positions<const W: usize, const H: usize>() -> impl Iterator<Item = Vecu> {
+    (0..H as u8).flat_map(|y| (0..W as u8).map(move |x| Vecu { x, y }))
+}
+
+/// A value for every segment of a maze of `W` by `H` segments, indexed by its position.
+///
+/// ### Description
+///
+/// Unlike a map, every segment always has a value, and the grid never allocates.
+/// Values are stored row by row, in the same order as the segments of a [crate::maze::Maze].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Grid<T, const W: usize, const H: usize> {
+    cells: [[T; W]; H],
+}
+
+impl<T: Copy, const W: usize, const H: usize> Grid<T, W, H> {
+    /// Creates a new grid where every segment has `value`.
+    pub fn new(value: T) -> Self {
+        Grid {
+            cells: [[value; W]; H],
+        }
+    }
+
+    /// Returns the value of the segment at `pos`.
+    pub fn get(&self, pos: Vecu) -> T {
+        self.cells.as_flattened()[index::<W, H>(pos)]
+    }
+
+    /// Sets the value of the segment at `pos`, and returns the previous value.
+    pub fn set(&mut self, pos: Vecu, value: T) -> T {
+        core::mem::replace(
+            &mut self.cells.as_flattened_mut()[index::<W, H>(pos)],
+            value,
+        )
+    }
+
+    /// Sets the value of every segment to `value`.
+    pub fn fill(&mut self, value: T) {
+        self.cells.as_flattened_mut().fill(value);
+    }
+
+    /// Returns every position with its value, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Vecu, T)> + '_ {
+        positions::<W, H>().zip(self.cells.as_flattened().iter().copied())
+    }
+}
+
+impl<T: Copy + Default, const W: usize, const H: usize> Default for Grid<T, W, H> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T, const W: usize, const H: usize> core::ops::Index<Vecu> for Grid<T, W, H> {
+    type Output = T;
+
+    fn index(&self, pos: Vecu) -> &T {
+        &self.cells.as_flattened()[index::<W, H>(pos)]
+    }
+}
+
+impl<T, const W: usize, const H: usize> core::ops::IndexMut<Vecu> for Grid<T, W, H> {
+    fn index_mut(&mut self, pos: Vecu) -> &mut T {
+        &mut self.cells.as_flattened_mut()[index::<W, H>(pos)]
+    }
+}
+
+/// A [Grid] of booleans for a maze of `W` by `H` segments, which stores one bit per segment.
+///
+/// ### Implementation
+///
+/// Every row is stored as a single 64-bit word, so the grid can be at most 64 segments wide,
+/// which fits both classic and half-size mazes. Clearing and counting is done a row at a time.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BitGrid<const W: usize, const H: usize> {
+    rows: [u64; H],
+}
+
+impl<const W: usize, const H: usize> BitGrid<W, H> {
+    /// Creates a new grid where no segment is set.
+    /// Fails to compile if `W` is larger than 64.
+    pub fn new() -> Self {
+        const {
+            assert!(
+                W <= u64::BITS as usize,
+                "BitGrid is at most 64 segments wide"
+            )
+        };
+        BitGrid { rows: [0; H] }
+    }
+
+    /// Returns the row and the mask of the bit of `pos`.
+    fn bit(pos: Vecu) -> (usize, u64) {
+        debug_assert!(
+            (pos.x as usize) < W && (pos.y as usize) < H,
+            "Position is outside the grid"
+        );
+
+        (pos.y as usize, 1 << pos.x)
+    }
+
+    /// Returns whether the segment at `pos` is set.
+    pub fn get(&self, pos: Vecu) -> bool {
+        let (y, mask) = Self::bit(pos);
+        self.rows[y] & mask != 0
+    }
+
+    /// Sets whether the segment at `pos` is set, and returns whether it was set before.
+    pub fn set(&mut self, pos: Vecu, value: bool) -> bool {
+        let (y, mask) = Self::bit(pos);
+        let row = &mut self.rows[y];
+        let previous = *row & mask != 0;
+
+        if value {
+            *row |= mask;
+        } else {
+            *row &= !mask;
+        }
+        previous
+    }
+
+    /// Unsets every segment.
+    pub fn clear(&mut self) {
+        self.rows.fill(0);
+    }
+
+    /// Returns the amount of segments that are set.
+    pub fn count(&self) -> usize {
+        self.rows.iter().map(|row| row.count_ones() as usize).sum()
+    }
+
+    /// Returns the positions of the segments that are set, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = Vecu> + '_ {
+        positions::<W, H>().filter(|pos| self.get(*pos))
+    }
+}
+
+impl<const W: usize, const H: usize> Default for BitGrid<W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid::{BitGrid, Grid};
+    use crate::vec::Vecu;
+
+    #[test]
+    fn grid() {
+        let mut grid: Grid<u16, 3, 2> = Grid::new(7);
+
+        assert_eq!(7, grid.set(Vecu { x: 2, y: 1 }, 4));
+        assert_eq!(4, grid.get(Vecu { x: 2, y: 1 }));
+        grid[Vecu { x: 0, y: 1 }] += 1;
+        assert_eq!(8, grid[Vecu { x: 0, y: 1 }]);
+
+        let values: Vec<(Vecu, u16)> = grid.iter().collect();
+        assert_eq!(6, values.len());
+        assert_eq!((Vecu { x: 1, y: 0 }, 7), values[1]);
+        assert_eq!((Vecu { x: 0, y: 1 }, 8), values[3]);
+        assert_eq!((Vecu { x: 2, y: 1 }, 4), values[5]);
+
+        grid.fill(0);
+        assert!(grid.iter().all(|(_, value)| value == 0));
+    }
+
+    #[test]
+    fn bit_grid() {
+        let mut grid: BitGrid<64, 2> = BitGrid::new();
+        let set = [
+            Vecu { x: 0, y: 0 },
+            Vecu { x: 63, y: 0 },
+            Vecu { x: 1, y: 1 },
+            Vecu { x: 62, y: 1 },
+        ];
+
+        for pos in set {
+            assert!(!grid.set(pos, true));
+        }
+        assert!(grid.set(Vecu { x: 63, y: 0 }, true));
+        assert!(!grid.get(Vecu { x: 63, y: 1 }));
+        assert_eq!(4, grid.count());
+        assert_eq!(set.to_vec(), grid.iter().collect::<Vec<_>>());
+
+        assert!(grid.set(Vecu { x: 0, y: 0 }, false));
+        assert!(!grid.get(Vecu { x: 0, y: 0 }));
+        assert_eq!(3, grid.count());
+
+        grid.clear();
+        assert_eq!(0, grid.count());
+    }
+}
@@ -0,0 +1,151 @@
+use crate::maze::{Relative, Wall};
+use crate::physics::Wheels;
+use crate::MAZE_BLOCK_M;
+
+pub mod explorer;
+#[cfg(feature = "rp2040")]
+pub mod rp2040;
+pub mod sim;
+
+/// The distance in m from the center of the mouse below which a wall sensor reading is
+/// considered to be a wall of the current segment, used by [observe].
+///
+/// A wall of the current segment is about half a segment away, while the next wall in the same
+/// direction is at least one and a half segments away.
+pub const WALL_THRESHOLD_M: f64 = MAZE_BLOCK_M * 0.75;
+
+/// The distances measured by the wall sensors of the mouse.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Distances {
+    /// The distance in m from the center of the mouse to the wall in front of it, or `None` if
+    /// no wall is in range.
+    pub front: Option<f64>,
+    /// The distance in m from the center of the mouse to the wall to its left, or `None` if no
+    /// wall is in range.
+    pub left: Option<f64>,
+    /// The distance in m from the center of the mouse to the wall to its right, or `None` if no
+    /// wall is in range.
+    pub right: Option<f64>,
+}
+
+/// Sensors that measure the distance to the walls in front of, to the left of and to the right
+/// of the mouse.
+pub trait WallSensors {
+    type Error;
+
+    /// Measures the distances to the walls around the mouse.
+    fn distances(&mut self) -> Result<Distances, Self::Error>;
+}
+
+/// The motors that drive the left and right wheel.
+pub trait DriveMotors {
+    type Error;
+
+    /// Sets the duty cycle of both motors, between -1 for full power backwards and 1 for full
+    /// power forwards. Values outside this range are clamped.
+    fn set_duty(&mut self, duty: Wheels) -> Result<(), Self::Error>;
+}
+
+/// Encoders that measure how far the left and right wheel have turned.
+pub trait WheelEncoders {
+    type Error;
+
+    /// Returns the distance in m both wheels have travelled over the floor since the start,
+    /// negative when driving backwards.
+    fn travelled(&mut self) -> Result<Wheels, Self::Error>;
+}
+
+/// A gyroscope that measures how fast the mouse rotates around its vertical axis.
+pub trait Gyro {
+    type Error;
+
+    /// Returns the angular velocity in rad/s, positive when the mouse turns to its right, like
+    /// [crate::physics::Physics::angular_velocity].
+    fn angular_velocity(&mut self) -> Result<f64, Self::Error>;
+}
+
+/// A mouse with every sensor and actuator, such as a [sim::SimulatedMouse] or [Hardware].
+pub trait Mouse: WallSensors + DriveMotors + WheelEncoders + Gyro {}
+
+impl<T: WallSensors + DriveMotors + WheelEncoders + Gyro> Mouse for T {}
+
+/// Separate sensors and actuators that together form a [Mouse], like the peripherals of the
+/// robot.
+pub struct Hardware<S, M, E, G> {
+    pub sensors: S,
+    pub motors: M,
+    pub encoders: E,
+    pub gyro: G,
+}
+
+impl<S: WallSensors, M, E, G> WallSensors for Hardware<S, M, E, G> {
+    type Error = S::Error;
+
+    fn distances(&mut self) -> Result<Distances, S::Error> {
+        self.sensors.distances()
+    }
+}
+
+impl<S, M: DriveMotors, E, G> DriveMotors for Hardware<S, M, E, G> {
+    type Error = M::Error;
+
+    fn set_duty(&mut self, duty: Wheels) -> Result<(), M::Error> {
+        self.motors.set_duty(duty)
+    }
+}
+
+impl<S, M, E: WheelEncoders, G> WheelEncoders for Hardware<S, M, E, G> {
+    type Error = E::Error;
+
+    fn travelled(&mut self) -> Result<Wheels, E::Error> {
+        self.encoders.travelled()
+    }
+}
+
+impl<S, M, E, G: Gyro> Gyro for Hardware<S, M, E, G> {
+    type Error = G::Error;
+
+    fn angular_velocity(&mut self) -> Result<f64, G::Error> {
+        self.gyro.angular_velocity()
+    }
+}
+
+/// Reads the walls around the segment the mouse is in using `sensors`.
+///
+/// ### Description
+///
+/// Readings closer than `threshold` are considered present walls, and readings further away or
+/// out of range are considered open. The wall behind the mouse cannot be seen, and is
+/// [Wall::Unknown]. The result can be passed to [crate::maze::Maze::update_walls] or
+/// [crate::maze::evidence::Evidence::observe].
+///
+/// ### Arguments
+///
+/// - `sensors` - The wall sensors.
+/// - `heading` - The direction the mouse is facing.
+/// - `threshold` - The distance in m below which a reading is a wall, such as [WALL_THRESHOLD_M].
+///
+/// ### Returns
+///
+/// The walls in the order of [Relative].
+pub fn observe<S: WallSensors>(
+    sensors: &mut S,
+    heading: Relative,
+    threshold: f64,
+) -> Result<[Wall; 4], S::Error> {
+    let distances = sensors.distances()?;
+    let mut walls = [Wall::Unknown; 4];
+
+    for (dir, distance) in [
+        (heading, distances.front),
+        (heading.left(), distances.left),
+        (heading.right(), distances.right),
+    ] {
+        walls[dir as usize] = match distance {
+            Some(distance) if distance < threshold => Wall::Present,
+            _ => Wall::Open,
+        };
+    }
+
+    Ok(walls)
+}
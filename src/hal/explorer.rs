@@ -0,0 +1,450 @@
+use crate::control::{DriveController, Tuning, Velocity};
+use crate::floodfill::UNREACHABLE;
+use crate::hal::{observe, DriveMotors, Gyro, Mouse, WallSensors, WheelEncoders};
+use crate::maze::evidence::Evidence;
+use crate::maze::{Maze, Relative, Wall};
+use crate::path::Path;
+use crate::pathfinder;
+use crate::pathfinder::Scratch;
+use crate::vec::Vecu;
+use crate::MAZE_BLOCK_M;
+use core::f64::consts::{FRAC_PI_2, PI};
+
+/// The forward velocity in m/s at which the mouse drives between segments.
+const CRUISE_SPEED_MS: f64 = 0.3;
+/// The forward acceleration in m/s² used to slow down towards the center of a segment.
+const ACCELERATION_MS2: f64 = 1.;
+/// The angular velocity in rad/s at which the mouse turns in place.
+const TURN_SPEED_RADS: f64 = 4.;
+/// The angular acceleration in rad/s² used to slow down towards the end of a turn.
+const ANGULAR_ACCELERATION_RADS2: f64 = 20.;
+/// The angular velocity in rad/s per radian the heading is off while driving straight.
+const HEADING_GAIN: f64 = 10.;
+/// The distance in m from the center of a segment at which the mouse has arrived.
+const ARRIVAL_TOLERANCE_M: f64 = 0.002;
+/// The angle in radians from the target heading at which a turn is finished.
+const TURN_TOLERANCE_RAD: f64 = 0.02;
+/// The amount of times the walls are read in every segment, once per update.
+const READINGS: u8 = 3;
+
+/// An error of the [Mouse] driven by an [Explorer].
+pub enum Error<R: Mouse> {
+    /// Reading the wall sensors failed.
+    Sensors(<R as WallSensors>::Error),
+    /// Setting the duty cycle of the motors failed.
+    Motors(<R as DriveMotors>::Error),
+    /// Reading the wheel encoders failed.
+    Encoders(<R as WheelEncoders>::Error),
+    /// Reading the gyroscope failed.
+    Gyro(<R as Gyro>::Error),
+    /// The mouse left the maze, or drove more segments than its path can hold.
+    Maze(crate::error::Error),
+}
+
+impl<R: Mouse> core::fmt::Debug for Error<R>
+where
+    <R as WallSensors>::Error: core::fmt::Debug,
+    <R as DriveMotors>::Error: core::fmt::Debug,
+    <R as WheelEncoders>::Error: core::fmt::Debug,
+    <R as Gyro>::Error: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::Sensors(e) => f.debug_tuple("Sensors").field(e).finish(),
+            Error::Motors(e) => f.debug_tuple("Motors").field(e).finish(),
+            Error::Encoders(e) => f.debug_tuple("Encoders").field(e).finish(),
+            Error::Gyro(e) => f.debug_tuple("Gyro").field(e).finish(),
+            Error::Maze(e) => f.debug_tuple("Maze").field(e).finish(),
+        }
+    }
+}
+
+/// What the mouse is doing.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Motion {
+    /// Turning in place before driving to the neighbouring segment.
+    Turn { to: Vecu },
+    /// Driving straight to the neighbouring segment.
+    Forward { to: Vecu },
+    /// Standing still, as the mouse is in the goal or cannot reach it.
+    Stopped,
+}
+
+/// Returns the heading in radians of a mouse facing `relative`, like [crate::physics::Pose].
+fn angle(relative: Relative) -> f64 {
+    (relative as usize as f64 - 1.) * FRAC_PI_2
+}
+
+/// Drives a [Mouse] through a maze whose walls it does not know yet, until it reaches the goal.
+///
+/// ### Description
+///
+/// The explorer makes the same decisions as a [crate::sim::Simulation], using
+/// [pathfinder::next_with], but drives the mouse between the segments using its motors, and reads
+/// the walls using its sensors. The mouse starts in the center of the origin segment facing
+/// [Relative::South]. Every move, it turns in place towards the next segment, and drives
+/// straight until it is in the center of it. Once there, it stops and observes the walls
+/// [READINGS] times using [observe].
+///
+/// Readings are counted by [Evidence] instead of being written to the maze directly, such that
+/// a single misread is outvoted by the other readings of the segment, and a wall that was
+/// committed wrongly is retracted by later readings instead of stopping the exploration.
+///
+/// [Explorer::update] runs a single period of the [DriveController], and should be called at a
+/// fixed rate. The same explorer runs on the robot and against a [crate::hal::sim::SimulatedMouse].
+///
+/// ### Implementation
+///
+/// The heading is found by integrating the angular velocity of the gyroscope, and the distance
+/// driven by averaging the distance both wheels have travelled. Turning in place does not change
+/// that average, so the distance to the center of the next segment is measured from the start,
+/// which keeps small errors of every move from adding up.
+pub struct Explorer<const W: usize, const H: usize> {
+    maze: Maze<W, H>,
+    evidence: Evidence<W, H>,
+    path: Path<W, H>,
+    scratch: Scratch<W, H>,
+    /// The route to the nearest unvisited segment when stuck.
+    route: Path<W, H>,
+    /// The amount of segments of `route` that have been driven to.
+    routed: usize,
+    heading: Relative,
+    controller: DriveController,
+    motion: Motion,
+    /// The amount of times the walls of the segment the mouse is in have been read.
+    readings: u8,
+    /// The heading in radians measured by the gyroscope.
+    angle: f64,
+    /// The heading in radians the mouse should face.
+    target_angle: f64,
+    /// The distance in m the mouse should have driven straight once it arrives.
+    target_distance: f64,
+}
+
+impl<const W: usize, const H: usize> Explorer<W, H> {
+    /// Creates a new explorer with the mouse at the origin.
+    ///
+    /// ### Arguments
+    ///
+    /// - `maze` - The known maze, usually without walls, of which the goal is driven to.
+    /// - `track` - The distance between the wheels in m.
+    /// - `tuning` - The gains of the [DriveController].
+    pub fn new(maze: Maze<W, H>, track: f64, tuning: &Tuning) -> Self {
+        let mut path = Path::new();
+        path.append(Vecu::new());
+
+        Explorer {
+            maze,
+            // every segment is read several times, so the majority of the readings decides
+            evidence: Evidence::new(1),
+            path,
+            scratch: Scratch::new(),
+            route: Path::new(),
+            routed: 0,
+            heading: Relative::South,
+            controller: DriveController::new(track, tuning),
+            motion: Motion::Stopped,
+            readings: 0,
+            angle: angle(Relative::South),
+            target_angle: angle(Relative::South),
+            target_distance: 0.,
+        }
+    }
+
+    /// Returns the maze as known by the mouse.
+    pub fn maze(&self) -> &Maze<W, H> {
+        &self.maze
+    }
+
+    /// Returns the segments the mouse has driven to.
+    pub fn path(&self) -> &Path<W, H> {
+        &self.path
+    }
+
+    /// Returns the segment the mouse is in, or driving away from.
+    pub fn position(&self) -> Vecu {
+        self.path.head().expect("Failed to find path head")
+    }
+
+    /// Whether the mouse is in the goal.
+    pub fn is_done(&self) -> bool {
+        self.maze.goal().contains(self.position())
+    }
+
+    /// Reads the sensors of `mouse` and sets its motors for a single period of `dt` seconds.
+    ///
+    /// ### Returns
+    ///
+    /// Whether the mouse is still exploring. Once it is in the goal, or the goal cannot be
+    /// reached through the walls known so far, the mouse is held still and `false` is returned.
+    pub fn update<R: Mouse>(&mut self, mouse: &mut R, dt: f64) -> Result<bool, Error<R>> {
+        let travelled = mouse.travelled().map_err(Error::Encoders)?;
+        let angular_velocity = mouse.angular_velocity().map_err(Error::Gyro)?;
+        self.angle += angular_velocity * dt;
+        let distance = (travelled.left + travelled.right) / 2.;
+
+        if self.readings < READINGS {
+            let walls =
+                observe(mouse, self.heading, super::WALL_THRESHOLD_M).map_err(Error::Sensors)?;
+            let pos = self.position();
+            self.count(pos, walls).map_err(Error::Maze)?;
+            self.readings += 1;
+            if self.readings == READINGS {
+                self.motion = self.plan();
+            }
+        }
+
+        if let Motion::Turn { to } = self.motion {
+            if libm::fabs(self.target_angle - self.angle) < TURN_TOLERANCE_RAD {
+                self.motion = Motion::Forward { to };
+            }
+        }
+
+        if let Motion::Forward { to } = self.motion {
+            if self.target_distance - distance < ARRIVAL_TOLERANCE_M {
+                self.arrive(to).map_err(Error::Maze)?;
+            }
+        }
+
+        let target = match self.motion {
+            Motion::Turn { .. } => {
+                let error = self.target_angle - self.angle;
+                let speed = libm::sqrt(2. * ANGULAR_ACCELERATION_RADS2 * libm::fabs(error));
+                Velocity {
+                    forward: 0.,
+                    angular: speed.min(TURN_SPEED_RADS).copysign(error),
+                }
+            }
+            Motion::Forward { .. } => {
+                let remaining = (self.target_distance - distance).max(0.);
+                Velocity {
+                    forward: libm::sqrt(2. * ACCELERATION_MS2 * remaining).min(CRUISE_SPEED_MS),
+                    angular: HEADING_GAIN * (self.target_angle - self.angle),
+                }
+            }
+            Motion::Stopped => Velocity::default(),
+        };
+
+        let duty = self
+            .controller
+            .update(target, travelled, angular_velocity, dt);
+        mouse.set_duty(duty).map_err(Error::Motors)?;
+        Ok(self.motion != Motion::Stopped || self.readings < READINGS)
+    }
+
+    /// Counts the `walls` observed from `pos`, and forgets the route when a known wall changed.
+    fn count(&mut self, pos: Vecu, walls: [Wall; 4]) -> Result<(), crate::error::Error> {
+        if self.evidence.observe(&mut self.maze, pos.x, pos.y, walls)? {
+            self.route.clear();
+            self.routed = 0;
+        }
+        Ok(())
+    }
+
+    /// Moves the mouse to the neighbouring segment `to`, which it has driven into.
+    fn arrive(&mut self, to: Vecu) -> Result<(), crate::error::Error> {
+        let mut entry = [Wall::Unknown; 4];
+        entry[self.heading.opposite() as usize] = Wall::Open;
+
+        self.path.try_append(to)?;
+        self.count(to, entry)?;
+        self.readings = 0;
+        self.motion = Motion::Stopped;
+        Ok(())
+    }
+
+    /// Returns how the mouse should move to the next segment, like [crate::sim::Simulation::step].
+    fn plan(&mut self) -> Motion {
+        let pos = self.position();
+        if self.is_done()
+            || self.maze.segment(pos.x, pos.y).distance == UNREACHABLE
+            || self.path.len() == self.path.capacity()
+        {
+            return Motion::Stopped;
+        }
+
+        if self.routed == self.route.len() && !self.route.is_empty() {
+            self.route.clear();
+            self.routed = 0;
+        }
+
+        let to = match self.route.segment(self.routed) {
+            Some(to) => {
+                self.routed += 1;
+                to
+            }
+            None => match pathfinder::next_with(&self.maze, &self.path, &mut self.scratch) {
+                Some(segment) => segment.pos(),
+                None => {
+                    let route = self.scratch.route().as_slice();
+                    if route.is_empty() {
+                        return Motion::Stopped;
+                    }
+                    self.route.append_all(route);
+                    self.routed = 1;
+                    route[0]
+                }
+            },
+        };
+
+        let heading = Relative::between(pos, to).expect("Mouse can only drive to neighbours");
+        // the amount of quarter turns to the right, turning around to the right
+        let quarters = (heading as usize + 4 - self.heading as usize) % 4;
+        self.target_angle += match quarters {
+            3 => -FRAC_PI_2,
+            2 => PI,
+            quarters => quarters as f64 * FRAC_PI_2,
+        };
+        self.target_distance += MAZE_BLOCK_M;
+        self.heading = heading;
+
+        if quarters == 0 {
+            Motion::Forward { to }
+        } else {
+            Motion::Turn { to }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::control::Tuning;
+    use crate::hal::explorer::Explorer;
+    use crate::hal::sim::SimulatedMouse;
+    use crate::hal::{
+        Distances, DriveMotors, Gyro, Mouse, WallSensors, WheelEncoders, WALL_THRESHOLD_M,
+    };
+    use crate::maze::generate::{generate, Algorithm};
+    use crate::maze::Maze;
+    use crate::physics::{Physics, Robot, Wheels};
+    use crate::sim::{Sensors, Simulation};
+    use crate::MAZE_BLOCK_M;
+    use core::convert::Infallible;
+
+    const DT: f64 = 0.001;
+
+    /// A simulated mouse of which the wall sensors misread a wall every few readings.
+    struct Noisy {
+        mouse: SimulatedMouse<16, 16>,
+        readings: u32,
+    }
+
+    /// Returns a wall reading that says the opposite of `distance`.
+    fn misread(distance: Option<f64>) -> Option<f64> {
+        match distance {
+            Some(distance) if distance < WALL_THRESHOLD_M => None,
+            _ => Some(MAZE_BLOCK_M / 4.),
+        }
+    }
+
+    impl WallSensors for Noisy {
+        type Error = Infallible;
+
+        fn distances(&mut self) -> Result<Distances, Infallible> {
+            let mut distances = self.mouse.distances()?;
+            self.readings += 1;
+            if self.readings.is_multiple_of(5) {
+                distances.front = misread(distances.front);
+            }
+            if self.readings % 7 == 3 {
+                distances.left = misread(distances.left);
+            }
+            Ok(distances)
+        }
+    }
+
+    impl DriveMotors for Noisy {
+        type Error = Infallible;
+
+        fn set_duty(&mut self, duty: Wheels) -> Result<(), Infallible> {
+            self.mouse.set_duty(duty)
+        }
+    }
+
+    impl WheelEncoders for Noisy {
+        type Error = Infallible;
+
+        fn travelled(&mut self) -> Result<Wheels, Infallible> {
+            self.mouse.travelled()
+        }
+    }
+
+    impl Gyro for Noisy {
+        type Error = Infallible;
+
+        fn angular_velocity(&mut self) -> Result<f64, Infallible> {
+            self.mouse.angular_velocity()
+        }
+    }
+
+    /// Explores `real` with the mouse returned by `mouse`, and asserts that it reaches the goal
+    /// without hitting a wall, and that every wall it knows matches `real`.
+    fn explore<M: Mouse>(
+        real: &Maze<16, 16>,
+        mouse: impl FnOnce(SimulatedMouse<16, 16>) -> M,
+        simulated: impl Fn(&mut M) -> &mut SimulatedMouse<16, 16>,
+    ) -> Explorer<16, 16>
+    where
+        <M as WallSensors>::Error: core::fmt::Debug,
+        <M as DriveMotors>::Error: core::fmt::Debug,
+        <M as WheelEncoders>::Error: core::fmt::Debug,
+        <M as Gyro>::Error: core::fmt::Debug,
+    {
+        let robot = Robot::default();
+        let mut mouse = mouse(SimulatedMouse::new(Physics::new(real.clone(), robot, DT)));
+        let mut explorer = Explorer::new(
+            Maze::with_goal(*real.goal()),
+            robot.track,
+            &Tuning::default(),
+        );
+
+        let mut ticks = 0;
+        while explorer.update(&mut mouse, DT).unwrap() {
+            assert!(simulated(&mut mouse).step().is_empty(), "Mouse hit a wall");
+            ticks += 1;
+            assert!(ticks < 1_000_000, "Mouse did not reach the goal");
+        }
+
+        assert!(explorer.is_done());
+        assert_eq!(
+            explorer.position(),
+            simulated(&mut mouse).physics().segment()
+        );
+        for x in 0..16 {
+            for y in 0..16 {
+                let known = explorer.maze().segment(x, y).walls;
+                let walls = real.segment(x, y).walls;
+                for (known, wall) in known.iter().zip(walls.iter()) {
+                    assert!(!known.is_known() || known == wall);
+                }
+            }
+        }
+
+        explorer
+    }
+
+    #[test]
+    fn reaches_goal() {
+        for seed in 0..3 {
+            let real: Maze<16, 16> = generate(Algorithm::Backtracker, seed, 0.1);
+            let explorer = explore(&real, |mouse| mouse, |mouse| mouse);
+
+            // the mouse sees the same walls as a simulated one with front sensors
+            let mut simulation = Simulation::new(real, Sensors::Front);
+            assert!(simulation.run(u32::MAX));
+            assert_eq!(simulation.path().as_slice(), explorer.path().as_slice());
+        }
+    }
+
+    #[test]
+    fn misreads() {
+        for seed in 0..3 {
+            let real: Maze<16, 16> = generate(Algorithm::Prim, seed, 0.1);
+            explore(
+                &real,
+                |mouse| Noisy { mouse, readings: 0 },
+                |noisy| &mut noisy.mouse,
+            );
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use crate::hal::{Distances, DriveMotors, Gyro, WallSensors, WheelEncoders};
+use crate::physics::Wheels;
+use core::cell::Cell;
+use core::convert::Infallible;
+use critical_section::Mutex;
+use embedded_hal::digital::{Error as _, OutputPin, PinState};
+use embedded_hal::i2c::{Error as _, I2c};
+use embedded_hal::pwm::{Error as _, SetDutyCycle};
+use rp2040_hal::adc::{Adc, AdcChannel};
+
+/// An error that occurred while talking to the hardware of the robot.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// A conversion of the ADC failed.
+    Adc,
+    /// Setting the duty cycle of a motor failed.
+    Pwm(embedded_hal::pwm::ErrorKind),
+    /// Setting the direction pin of a motor failed.
+    Digital(embedded_hal::digital::ErrorKind),
+    /// Talking to the gyroscope failed.
+    I2c(embedded_hal::i2c::ErrorKind),
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::Adc => write!(f, "ADC conversion failed"),
+            Error::Pwm(kind) => write!(f, "failed to set the motor duty cycle: {}", kind),
+            Error::Digital(kind) => write!(f, "failed to set the motor direction: {}", kind),
+            Error::I2c(kind) => write!(f, "failed to read the gyroscope: {}", kind),
+        }
+    }
+}
+
+/// Converts the readings of an infrared wall sensor to distances.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Calibration {
+    /// The distance in m at a reading of 1. The reflected light falls off with the square of the
+    /// distance, so the distance is `scale / sqrt(reading)`.
+    pub scale: f64,
+    /// The lowest reading that is considered a wall. Lower readings are out of range.
+    pub min_reading: u16,
+}
+
+impl Calibration {
+    /// Returns the distance in m for the `reading`, or `None` if it is out of range.
+    pub fn distance(&self, reading: u16) -> Option<f64> {
+        if reading < self.min_reading || reading == 0 {
+            return None;
+        }
+        Some(self.scale / libm::sqrt(reading as f64))
+    }
+}
+
+/// Infrared wall sensors read by the ADC of the RP2040.
+pub struct IrSensors<F, L, R> {
+    adc: Adc,
+    front: F,
+    left: L,
+    right: R,
+    calibration: Calibration,
+}
+
+impl<F: AdcChannel, L: AdcChannel, R: AdcChannel> IrSensors<F, L, R> {
+    /// Creates new wall sensors from the ADC and the ADC pins of every sensor, which share the
+    /// same `calibration`.
+    pub fn new(adc: Adc, front: F, left: L, right: R, calibration: Calibration) -> Self {
+        IrSensors {
+            adc,
+            front,
+            left,
+            right,
+            calibration,
+        }
+    }
+}
+
+impl<F: AdcChannel, L: AdcChannel, R: AdcChannel> WallSensors for IrSensors<F, L, R> {
+    type Error = Error;
+
+    fn distances(&mut self) -> Result<Distances, Error> {
+        let front = self.adc.read(&mut self.front).map_err(|_| Error::Adc)?;
+        let left = self.adc.read(&mut self.left).map_err(|_| Error::Adc)?;
+        let right = self.adc.read(&mut self.right).map_err(|_| Error::Adc)?;
+
+        Ok(Distances {
+            front: self.calibration.distance(front),
+            left: self.calibration.distance(left),
+            right: self.calibration.distance(right),
+        })
+    }
+}
+
+/// Motors driven by an H-bridge with a PWM input for the speed and a pin for the direction of
+/// every motor, such as the PWM channels and GPIO pins of the RP2040.
+pub struct Motors<L, R, DL, DR> {
+    left: L,
+    right: R,
+    left_direction: DL,
+    right_direction: DR,
+}
+
+impl<L, R, DL, DR> Motors<L, R, DL, DR>
+where
+    L: SetDutyCycle,
+    R: SetDutyCycle,
+    DL: OutputPin,
+    DR: OutputPin,
+{
+    /// Creates new motors from the PWM channel and the direction pin of both motors.
+    /// A high direction pin drives the wheel forward.
+    pub fn new(left: L, right: R, left_direction: DL, right_direction: DR) -> Self {
+        Motors {
+            left,
+            right,
+            left_direction,
+            right_direction,
+        }
+    }
+}
+
+/// Sets the direction pin and the duty cycle of a single motor to `duty`.
+fn set_motor<P: SetDutyCycle, D: OutputPin>(
+    pwm: &mut P,
+    direction: &mut D,
+    duty: f64,
+) -> Result<(), Error> {
+    direction
+        .set_state(PinState::from(duty >= 0.))
+        .map_err(|e| Error::Digital(e.kind()))?;
+
+    let duty = (duty.abs().min(1.) * u16::MAX as f64) as u16;
+    pwm.set_duty_cycle_fraction(duty, u16::MAX)
+        .map_err(|e| Error::Pwm(e.kind()))
+}
+
+impl<L, R, DL, DR> DriveMotors for Motors<L, R, DL, DR>
+where
+    L: SetDutyCycle,
+    R: SetDutyCycle,
+    DL: OutputPin,
+    DR: OutputPin,
+{
+    type Error = Error;
+
+    fn set_duty(&mut self, duty: Wheels) -> Result<(), Error> {
+        set_motor(&mut self.left, &mut self.left_direction, duty.left)?;
+        set_motor(&mut self.right, &mut self.right_direction, duty.right)
+    }
+}
+
+/// A wheel of the robot.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Wheel {
+    Left,
+    Right,
+}
+
+/// Quadrature wheel encoders, of which the ticks are counted by GPIO interrupts.
+///
+/// ### Description
+///
+/// The encoders are meant to be stored in a `static`, such that the interrupt handler can call
+/// [Encoders::tick] while the main loop reads the travelled distance through a shared reference.
+pub struct Encoders {
+    /// The ticks of the left and right wheel.
+    ticks: Mutex<Cell<(i32, i32)>>,
+    meters_per_tick: f64,
+}
+
+impl Encoders {
+    /// Creates new encoders that have not ticked yet.
+    ///
+    /// ### Arguments
+    ///
+    /// - `meters_per_tick` - The distance a wheel travels over the floor per tick.
+    pub const fn new(meters_per_tick: f64) -> Self {
+        Encoders {
+            ticks: Mutex::new(Cell::new((0, 0))),
+            meters_per_tick,
+        }
+    }
+
+    /// Counts a single tick of `wheel`, which turned forward if `forward` is set.
+    /// Meant to be called from the interrupt handler of the encoder pins.
+    pub fn tick(&self, wheel: Wheel, forward: bool) {
+        let step = if forward { 1 } else { -1 };
+
+        critical_section::with(|cs| {
+            let ticks = self.ticks.borrow(cs);
+            let (left, right) = ticks.get();
+            ticks.set(match wheel {
+                Wheel::Left => (left.wrapping_add(step), right),
+                Wheel::Right => (left, right.wrapping_add(step)),
+            });
+        });
+    }
+}
+
+impl WheelEncoders for &Encoders {
+    type Error = Infallible;
+
+    fn travelled(&mut self) -> Result<Wheels, Infallible> {
+        let (left, right) = critical_section::with(|cs| self.ticks.borrow(cs).get());
+
+        Ok(Wheels {
+            left: left as f64 * self.meters_per_tick,
+            right: right as f64 * self.meters_per_tick,
+        })
+    }
+}
+
+/// The I2C address of the MPU-6050.
+const MPU6050_ADDRESS: u8 = 0x68;
+/// The power management register of the MPU-6050.
+const MPU6050_PWR_MGMT_1: u8 = 0x6b;
+/// The first of the two registers of the rotation around the z-axis of the MPU-6050.
+const MPU6050_GYRO_ZOUT_H: u8 = 0x47;
+/// The reading of the MPU-6050 per degree per second, in its default range of 250 °/s.
+const MPU6050_LSB_PER_DEGREE: f64 = 131.;
+
+/// An MPU-6050 gyroscope connected over I2C, mounted flat with its z-axis pointing up.
+pub struct Mpu6050<I> {
+    i2c: I,
+}
+
+impl<I: I2c> Mpu6050<I> {
+    /// Wakes up the gyroscope on `i2c`.
+    pub fn new(mut i2c: I) -> Result<Self, Error> {
+        i2c.write(MPU6050_ADDRESS, &[MPU6050_PWR_MGMT_1, 0])
+            .map_err(|e| Error::I2c(e.kind()))?;
+        Ok(Mpu6050 { i2c })
+    }
+}
+
+impl<I: I2c> Gyro for Mpu6050<I> {
+    type Error = Error;
+
+    fn angular_velocity(&mut self) -> Result<f64, Error> {
+        let mut bytes = [0; 2];
+        self.i2c
+            .write_read(MPU6050_ADDRESS, &[MPU6050_GYRO_ZOUT_H], &mut bytes)
+            .map_err(|e| Error::I2c(e.kind()))?;
+
+        // the gyroscope measures counter-clockwise rotation when seen from above, which is a
+        // turn to the left of the mouse
+        let degrees = i16::from_be_bytes(bytes) as f64 / MPU6050_LSB_PER_DEGREE;
+        Ok(-degrees.to_radians())
+    }
+}
@@ -0,0 +1,235 @@
+use crate::hal::{Distances, DriveMotors, Gyro, WallSensors, WheelEncoders};
+use crate::maze::Relative;
+use crate::physics::{wall_ends, Contact, Physics, Wheels, WALL_THICKNESS_M};
+use crate::vec::Vecf;
+use crate::MAZE_BLOCK_M;
+use core::convert::Infallible;
+
+/// The distance in m up to which the simulated wall sensors can see a wall.
+pub const SENSOR_RANGE_M: f64 = 0.3;
+
+/// A mouse whose sensors, motors and encoders are simulated using [Physics], such that code
+/// written against the [crate::hal] traits can be run without the robot.
+///
+/// ### Description
+///
+/// The motors keep the duty cycle they were last set to, and the mouse only moves when
+/// [SimulatedMouse::step] is called. The wall sensors measure the distance from the center of the
+/// mouse to the surface of the nearest wall straight ahead, to the left and to the right. Readings
+/// are exact, and never fail.
+pub struct SimulatedMouse<const W: usize, const H: usize> {
+    physics: Physics<W, H>,
+    duty: Wheels,
+    travelled: Wheels,
+}
+
+impl<const W: usize, const H: usize> SimulatedMouse<W, H> {
+    /// Creates a new simulated mouse, of which the motors are turned off.
+    pub fn new(physics: Physics<W, H>) -> Self {
+        SimulatedMouse {
+            physics,
+            duty: Wheels::default(),
+            travelled: Wheels::default(),
+        }
+    }
+
+    /// Returns the simulation of the mouse.
+    pub fn physics(&self) -> &Physics<W, H> {
+        &self.physics
+    }
+
+    /// Simulates a single step of [Physics::dt] seconds with the current duty cycle, and returns
+    /// the walls the mouse collided with.
+    pub fn step(&mut self) -> &[Contact] {
+        let max_torque = self.physics.robot().max_torque;
+        let dt = self.physics.dt();
+
+        self.physics.step(Wheels {
+            left: self.duty.left * max_torque,
+            right: self.duty.right * max_torque,
+        });
+
+        let speeds = self.physics.wheel_speeds();
+        self.travelled.left += speeds.left * dt;
+        self.travelled.right += speeds.right * dt;
+        self.physics.contacts()
+    }
+
+    /// Returns the distance from the center of the mouse to the nearest wall at `angle` radians,
+    /// or `None` if there is no wall within [SENSOR_RANGE_M].
+    fn cast(&self, angle: f64) -> Option<f64> {
+        let p = self.physics.pose().position;
+        let d = Vecf {
+            x: libm::cos(angle),
+            y: libm::sin(angle),
+        };
+        let segment = self.physics.segment();
+        let reach = libm::ceil(SENSOR_RANGE_M / MAZE_BLOCK_M) as u8;
+        let mut nearest: Option<f64> = None;
+
+        let ys = segment.y.saturating_sub(reach)..=segment.y.saturating_add(reach).min(H as u8 - 1);
+        for y in ys {
+            let xs =
+                segment.x.saturating_sub(reach)..=segment.x.saturating_add(reach).min(W as u8 - 1);
+            for x in xs {
+                for relative in [
+                    Relative::North,
+                    Relative::East,
+                    Relative::South,
+                    Relative::West,
+                ] {
+                    // every inner wall is only checked once, from the segment west or north of it
+                    let outer = match relative {
+                        Relative::North => y == 0,
+                        Relative::West => x == 0,
+                        _ => true,
+                    };
+                    if !outer || !self.physics.is_wall(x, y, relative) {
+                        continue;
+                    }
+
+                    let (from, to) = wall_ends(x, y, relative);
+                    if let Some(distance) = hit(p, d, from, to) {
+                        if distance <= SENSOR_RANGE_M && nearest.is_none_or(|n| distance < n) {
+                            nearest = Some(distance);
+                        }
+                    }
+                }
+            }
+        }
+
+        nearest
+    }
+}
+
+/// Returns the distance from `p` in direction `d` to the surface of the wall with a center line
+/// from `from` to `to`, or `None` if the ray misses it.
+fn hit(p: Vecf, d: Vecf, from: Vecf, to: Vecf) -> Option<f64> {
+    let half = WALL_THICKNESS_M / 2.;
+    // the coordinates across and along the wall, for a vertical and a horizontal wall
+    let (p_across, p_along, d_across, d_along, wall, start, end) = if from.x == to.x {
+        (p.x, p.y, d.x, d.y, from.x, from.y, to.y)
+    } else {
+        (p.y, p.x, d.y, d.x, from.y, from.x, to.x)
+    };
+
+    if d_across == 0. {
+        return None;
+    }
+
+    let surface = wall - half * d_across.signum();
+    let distance = (surface - p_across) / d_across;
+    let along = p_along + distance * d_along;
+    if distance < 0. || along < start - half || along > end + half {
+        return None;
+    }
+    Some(distance)
+}
+
+impl<const W: usize, const H: usize> WallSensors for SimulatedMouse<W, H> {
+    type Error = Infallible;
+
+    fn distances(&mut self) -> Result<Distances, Infallible> {
+        let heading = self.physics.pose().heading;
+        let quarter = core::f64::consts::FRAC_PI_2;
+
+        Ok(Distances {
+            front: self.cast(heading),
+            left: self.cast(heading - quarter),
+            right: self.cast(heading + quarter),
+        })
+    }
+}
+
+impl<const W: usize, const H: usize> DriveMotors for SimulatedMouse<W, H> {
+    type Error = Infallible;
+
+    fn set_duty(&mut self, duty: Wheels) -> Result<(), Infallible> {
+        self.duty = Wheels {
+            left: duty.left.clamp(-1., 1.),
+            right: duty.right.clamp(-1., 1.),
+        };
+        Ok(())
+    }
+}
+
+impl<const W: usize, const H: usize> WheelEncoders for SimulatedMouse<W, H> {
+    type Error = Infallible;
+
+    fn travelled(&mut self) -> Result<Wheels, Infallible> {
+        Ok(self.travelled)
+    }
+}
+
+impl<const W: usize, const H: usize> Gyro for SimulatedMouse<W, H> {
+    type Error = Infallible;
+
+    fn angular_velocity(&mut self) -> Result<f64, Infallible> {
+        Ok(self.physics.angular_velocity())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hal::sim::SimulatedMouse;
+    use crate::hal::{observe, DriveMotors, Gyro, WallSensors, WheelEncoders, WALL_THRESHOLD_M};
+    use crate::maze::generate::{generate, Algorithm};
+    use crate::maze::{Maze, Relative, Wall};
+    use crate::physics::{Physics, Robot, Wheels, WALL_THICKNESS_M};
+    use crate::MAZE_BLOCK_M;
+
+    #[test]
+    fn sensors() {
+        // the start is walled in except towards the south, with a wall two segments down
+        let mut maze: Maze<4, 4> = Maze::new();
+        maze.update_walls(0, 0, [true, true, false, true]);
+        maze.update_walls(0, 1, [None, None, Some(true), None]);
+        let mut mouse = SimulatedMouse::new(Physics::new(maze, Robot::default(), 0.001));
+
+        let distances = mouse.distances().unwrap();
+        let side = MAZE_BLOCK_M / 2. - WALL_THICKNESS_M / 2.;
+        assert!((distances.left.unwrap() - side).abs() < 1e-9);
+        assert!((distances.right.unwrap() - side).abs() < 1e-9);
+        assert!((distances.front.unwrap() - (side + MAZE_BLOCK_M)).abs() < 1e-9);
+
+        assert_eq!(
+            [Wall::Unknown, Wall::Present, Wall::Open, Wall::Present],
+            observe(&mut mouse, Relative::South, WALL_THRESHOLD_M).unwrap()
+        );
+    }
+
+    #[test]
+    fn observes_real_walls() {
+        let real: Maze<16, 16> = generate(Algorithm::Prim, 2, 0.1);
+        let walls = real.segment(0, 0).walls;
+        let mut mouse = SimulatedMouse::new(Physics::new(real, Robot::default(), 0.001));
+
+        let observed = observe(&mut mouse, Relative::South, WALL_THRESHOLD_M).unwrap();
+        for dir in [Relative::South, Relative::East, Relative::West] {
+            assert_eq!(walls[dir as usize], observed[dir as usize]);
+        }
+    }
+
+    #[test]
+    fn drives() {
+        let mut mouse =
+            SimulatedMouse::new(Physics::new(Maze::<16, 16>::new(), Robot::default(), 0.001));
+
+        mouse
+            .set_duty(Wheels {
+                left: 2.,
+                right: 0.5,
+            })
+            .unwrap();
+        for _ in 0..100 {
+            mouse.step();
+        }
+
+        // the left wheel is driven harder, which turns the mouse to its right
+        let travelled = mouse.travelled().unwrap();
+        assert!(travelled.left > travelled.right);
+        assert!(travelled.right > 0.);
+        assert!(mouse.angular_velocity().unwrap() > 0.);
+        assert!(mouse.physics().pose().heading > core::f64::consts::FRAC_PI_2);
+    }
+}
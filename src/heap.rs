@@ -0,0 +1,175 @@
+/// The position in the heap of a node that is not in the heap.
+const ABSENT: usize = usize::MAX;
+
+/// An indexed binary min-heap of nodes with keys of type `K`, without any allocation.
+///
+/// ### Description
+///
+/// Nodes are identified by an index from `0` up to `L * W * H`, so there is room for `L` nodes
+/// for every segment of a maze of `W` by `H` segments. Every node is in the heap at most once,
+/// and the key of a node that is already in the heap can be changed, or the node removed,
+/// in logarithmic time.
+pub(crate) struct Heap<K, const L: usize, const W: usize, const H: usize> {
+    /// The nodes in heap order, with their keys.
+    entries: [[[(K, usize); L]; W]; H],
+    /// The position of every node in `entries`, or [ABSENT].
+    positions: [[[usize; L]; W]; H],
+    len: usize,
+}
+
+impl<K: Copy + Ord + Default, const L: usize, const W: usize, const H: usize> Heap<K, L, W, H> {
+    /// Returns a new empty heap.
+    pub(crate) fn new() -> Self {
+        Heap {
+            entries: [[[(K::default(), 0); L]; W]; H],
+            positions: [[[ABSENT; L]; W]; H],
+            len: 0,
+        }
+    }
+
+    /// Returns the node with the smallest key, and its key.
+    pub(crate) fn peek(&self) -> Option<(usize, K)> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let (key, node) = self.entries.as_flattened().as_flattened()[0];
+        Some((node, key))
+    }
+
+    /// Removes and returns the node with the smallest key, and its key.
+    pub(crate) fn pop(&mut self) -> Option<(usize, K)> {
+        let top = self.peek()?;
+        self.remove(top.0);
+        Some(top)
+    }
+
+    /// Adds `node` with `key` to the heap, or changes its key if it is already in the heap.
+    pub(crate) fn push(&mut self, node: usize, key: K) {
+        let position = self.positions.as_flattened().as_flattened()[node];
+
+        if position == ABSENT {
+            self.set(self.len, (key, node));
+            self.len += 1;
+            self.sift_up(self.len - 1);
+            return;
+        }
+
+        let old = self.entries.as_flattened().as_flattened()[position].0;
+        self.set(position, (key, node));
+        if key < old {
+            self.sift_up(position);
+        } else {
+            self.sift_down(position);
+        }
+    }
+
+    /// Removes `node` from the heap, if it is in the heap.
+    pub(crate) fn remove(&mut self, node: usize) {
+        let position = self.positions.as_flattened().as_flattened()[node];
+        if position == ABSENT {
+            return;
+        }
+
+        self.len -= 1;
+        self.positions.as_flattened_mut().as_flattened_mut()[node] = ABSENT;
+        if position == self.len {
+            return;
+        }
+
+        let last = self.entries.as_flattened().as_flattened()[self.len];
+        self.set(position, last);
+        self.sift_up(position);
+        self.sift_down(self.positions.as_flattened().as_flattened()[last.1]);
+    }
+
+    /// Stores `entry` at `position`, and records the position of its node.
+    fn set(&mut self, position: usize, entry: (K, usize)) {
+        self.entries.as_flattened_mut().as_flattened_mut()[position] = entry;
+        self.positions.as_flattened_mut().as_flattened_mut()[entry.1] = position;
+    }
+
+    /// Swaps the entries at positions `a` and `b`.
+    fn swap(&mut self, a: usize, b: usize) {
+        let entries = self.entries.as_flattened().as_flattened();
+        let (first, second) = (entries[a], entries[b]);
+        self.set(a, second);
+        self.set(b, first);
+    }
+
+    /// Moves the entry at `position` up until its parent has a smaller key.
+    fn sift_up(&mut self, mut position: usize) {
+        while position > 0 {
+            let parent = (position - 1) / 2;
+            let entries = self.entries.as_flattened().as_flattened();
+            if entries[parent].0 <= entries[position].0 {
+                break;
+            }
+
+            self.swap(parent, position);
+            position = parent;
+        }
+    }
+
+    /// Moves the entry at `position` down until its children have larger keys.
+    fn sift_down(&mut self, mut position: usize) {
+        loop {
+            let entries = self.entries.as_flattened().as_flattened();
+            let mut smallest = position;
+            for child in [2 * position + 1, 2 * position + 2] {
+                if child < self.len && entries[child].0 < entries[smallest].0 {
+                    smallest = child;
+                }
+            }
+
+            if smallest == position {
+                break;
+            }
+
+            self.swap(position, smallest);
+            position = smallest;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::heap::Heap;
+
+    #[test]
+    fn order() {
+        let mut heap: Heap<u32, 2, 3, 2> = Heap::new();
+        let keys = [7, 3, 9, 1, 12, 5, 8, 2, 11, 4, 10, 6];
+        for (node, key) in keys.iter().enumerate() {
+            heap.push(node, *key);
+        }
+
+        let mut popped = Vec::new();
+        while let Some((node, key)) = heap.pop() {
+            assert_eq!(keys[node], key);
+            popped.push(key);
+        }
+
+        assert_eq!((1..=12).collect::<Vec<_>>(), popped);
+        assert_eq!(None, heap.peek());
+    }
+
+    #[test]
+    fn update_and_remove() {
+        let mut heap: Heap<u32, 1, 4, 1> = Heap::new();
+        heap.push(0, 5);
+        heap.push(1, 3);
+        heap.push(2, 8);
+        heap.push(3, 6);
+
+        heap.push(2, 1);
+        assert_eq!(Some((2, 1)), heap.peek());
+
+        heap.push(2, 7);
+        heap.remove(1);
+        assert_eq!(Some((0, 5)), heap.pop());
+        assert_eq!(Some((3, 6)), heap.pop());
+        assert_eq!(Some((2, 7)), heap.pop());
+        assert_eq!(None, heap.pop());
+    }
+}
@@ -1,19 +1,35 @@
-/// The maze width.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+/// The width of the classic maze.
 pub const MAZE_WIDTH_U8: u8 = 16;
 pub const MAZE_WIDTH_USIZE: usize = 16;
 
-/// The maze height.
+/// The height of the classic maze.
 pub const MAZE_HEIGHT_U8: u8 = 16;
 pub const MAZE_HEIGHT_USIZE: usize = 16;
 
-/// The maze size.
+/// The number of segments in the classic maze.
 pub const MAZE_SIZE: usize = MAZE_WIDTH_USIZE * MAZE_HEIGHT_USIZE;
 
 /// The size of a block of a maze.
 pub const MAZE_BLOCK_M: f64 = 0.18;
 
-mod map;
+pub mod commands;
+pub mod control;
+pub mod dstar;
+pub mod error;
+pub mod explore;
+pub mod floodfill;
+pub mod goal;
+pub mod grid;
+pub mod hal;
+mod heap;
 pub mod maze;
 pub mod path;
 pub mod pathfinder;
+pub mod physics;
+pub mod search;
+pub mod sim;
+pub mod solver;
+pub mod speedrun;
 pub mod vec;
@@ -1,51 +1,38 @@
-use mouse::maze::Maze;
-use mouse::path::Path;
+use mouse::goal::Goal;
+use mouse::maze::{ClassicMaze, Maze};
+use mouse::path::ClassicPath;
 use mouse::pathfinder;
-use mouse::pathfinder::Target;
-use mouse::vec::Vecu;
+use mouse::physics::{Physics, Robot, Wheels};
 
 const DT: f64 = 0.01;
-const MU: f64 = 0.1;
-const M: f64 = 0.1;
-const W: f64 = 7.;
 
 fn main() {
-    let mut maze = Maze::new();
-    let mut first = Path::new();
-
-    first.append(Vecu::new());
-
-    let mut t = 0.;
-
-    let mut px;
-    let mut py;
-    let mut ax;
-    let mut ay;
-    let mut vx;
-    let mut vy;
-
-    loop {
-        let mut fresx = 0.;
-        let mut fresy = 0.;
-
-        ax = fresx / M;
-        ay = fresy / M;
-
-        vx = ax * DT;
-        vy = ay * DT;
-
-        px = vx * DT;
-        py = vy * DT;
-
-        t = t * DT;
+    let mut physics = Physics::new(ClassicMaze::new(), Robot::default(), DT);
+    let torques = Wheels {
+        left: 0.002,
+        right: 0.002,
+    };
+
+    // drive straight out of the start until the border of the maze is hit
+    while physics.time() < 2. {
+        if let Some(contact) = physics.step(torques).first().copied() {
+            println!("{:.2} -> hit {:?}", physics.time(), contact);
+            break;
+        }
 
-        println!("{t} -> {px} {py}");
+        println!(
+            "{:.2} -> {:?} at {:.3} m/s",
+            physics.time(),
+            physics.pose().position,
+            physics.velocity()
+        );
     }
 }
 
 fn _find() {
-    let mut maze = Maze::new();
-    let mut first = Path::new();
+    let mut maze = ClassicMaze::new();
+    let mut first = ClassicPath::new();
+    let mut route = ClassicPath::new();
 
     maze.update_walls(0, 0, [true, false, true, true]);
     maze.update_walls(1, 0, [true, false, false, false]);
@@ -54,7 +41,7 @@ fn _find() {
 
     // first deep dive
     loop {
-        let result = pathfinder::next(&maze, &first);
+        let result = pathfinder::next(&maze, &first, &mut route);
 
         match result {
             pathfinder::Result::Found(next) => {
@@ -65,22 +52,22 @@ fn _find() {
                 }
             }
             pathfinder::Result::Stuck(next) => {
-                first.append_all(&next);
-                pathfinder::update_distances(&mut maze, &first);
+                first.append_all(next.as_slice());
             }
         }
     }
 
     first.optimize();
 
-    let mut maze = Maze::with_walls(Target::Origin, maze);
-    let mut second = Path::new();
+    let maze = Maze::with_walls(Goal::origin(), maze);
+    let mut second = ClassicPath::new();
     second.append(first.head().unwrap());
-    second.append_all(&pathfinder::nearest_unvisited(&maze, &first));
+    pathfinder::nearest_unvisited(&maze, &first, &mut route);
+    second.append_all(route.as_slice());
 
     // second
     loop {
-        let result = pathfinder::next(&maze, &second);
+        let result = pathfinder::next(&maze, &second, &mut route);
 
         match result {
             pathfinder::Result::Found(next) => {
@@ -91,8 +78,7 @@ fn _find() {
                 }
             }
             pathfinder::Result::Stuck(next) => {
-                second.append_all(&next);
-                pathfinder::update_distances(&mut maze, &second);
+                second.append_all(next.as_slice());
             }
         }
     }
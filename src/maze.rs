@@ -1,77 +1,108 @@
-use crate::pathfinder::Target;
+use crate::error::Error;
+use crate::floodfill;
+use crate::floodfill::Queue;
+use crate::goal::Goal;
+use crate::grid::Grid;
 use crate::vec::Vecu;
-use crate::{MAZE_HEIGHT_U8, MAZE_SIZE, MAZE_WIDTH_U8};
+use crate::{MAZE_HEIGHT_USIZE, MAZE_WIDTH_USIZE};
 use core::slice::Iter;
 
-/// Represents the maze
-pub struct Maze {
-    segments: [Segment; MAZE_SIZE],
+pub mod ascii;
+pub mod evidence;
+#[cfg(feature = "std")]
+pub mod file;
+pub mod generate;
+pub mod maz;
+
+/// Represents a maze of `W` by `H` segments.
+/// Both dimensions may be at most [u8::MAX], as positions are stored in a [Vecu].
+#[derive(Clone)]
+pub struct Maze<const W: usize, const H: usize> {
+    segments: [[Segment; W]; H],
+    goal: Goal<W, H>,
 }
 
-/// Calculates the distance to the specified point
-fn maze_calc_distance(x: u8, y: u8, cx: i8, cy: i8) -> u8 {
-    (i8::abs((x as i8) - cx) + i8::abs((y as i8) - cy)) as u8
-}
+/// The classic 16x16 competition maze.
+pub type ClassicMaze = Maze<MAZE_WIDTH_USIZE, MAZE_HEIGHT_USIZE>;
+
+/// The half-size 32x32 competition maze.
+pub type HalfSizeMaze = Maze<32, 32>;
 
 /// Converts the position to the index for `segments`.
-fn xy_to_index(x: u8, y: u8) -> usize {
-    (x + y * MAZE_WIDTH_U8) as usize
+pub(crate) fn xy_to_index<const W: usize>(x: u8, y: u8) -> usize {
+    x as usize + y as usize * W
 }
 
 /// Converts the position to the index for `segments`.
-fn pos_to_index(pos: Vecu) -> usize {
-    xy_to_index(pos.x, pos.y)
+fn pos_to_index<const W: usize>(pos: Vecu) -> usize {
+    xy_to_index::<W>(pos.x, pos.y)
 }
 
 /// Creates a new Maze with the provided walls.
-fn with_walls_fn<T>(target: Target, walls: T) -> Maze
+fn with_walls_fn<const W: usize, const H: usize, T>(goal: Goal<W, H>, walls: T) -> Maze<W, H>
 where
-    T: Fn(u8, u8) -> [bool; 4],
+    T: Fn(u8, u8) -> [Wall; 4],
 {
-    let mut points = [Segment::new(); MAZE_SIZE];
-
-    let distances = match target {
-        Target::Center => [
-            |x, y| maze_calc_distance(x, y, 8, 8),
-            |x, y| maze_calc_distance(x, y, 7, 8),
-            |x, y| maze_calc_distance(x, y, 8, 7),
-            |x, y| maze_calc_distance(x, y, 7, 7),
-        ],
-        Target::Origin => [
-            |x, y| maze_calc_distance(x, y, 0, 0),
-            |_x, _y| u8::MAX,
-            |_x, _y| u8::MAX,
-            |_x, _y| u8::MAX,
-        ],
+    assert!(
+        W > 0 && H > 0 && W <= u8::MAX as usize && H <= u8::MAX as usize,
+        "Maze dimensions must be between 1 and 255"
+    );
+
+    let mut maze = Maze {
+        segments: [[Segment::new(); W]; H],
+        goal,
     };
 
-    for x in 0..MAZE_WIDTH_U8 {
-        for y in 0..MAZE_HEIGHT_U8 {
-            points[xy_to_index(x, y)] = Segment {
-                pos: Vecu { x, y },
-                distance: *distances.map(|f| f(x, y)).iter().min().unwrap(),
-                walls: walls(x, y),
-            };
+    for x in 0..W as u8 {
+        for y in 0..H as u8 {
+            maze.segments.as_flattened_mut()[xy_to_index::<W>(x, y)] =
+                Segment::with_walls(Vecu { x, y }, walls(x, y));
         }
     }
 
-    Maze { segments: points }
+    floodfill::flood(&mut maze);
+    maze
 }
 
-impl Maze {
+impl<const W: usize, const H: usize> Maze<W, H> {
     /// Creates a new maze centered around the center.
     pub fn new() -> Self {
-        with_walls_fn(Target::Center, |_x, _y| [false, false, false, false])
+        Self::with_goal(Goal::center())
+    }
+
+    /// Creates a new maze centered around the specified goal.
+    pub fn with_goal(goal: Goal<W, H>) -> Self {
+        with_walls_fn(goal, |_x, _y| [Wall::Unknown; 4])
     }
 
-    /// Creates a new maze with the specified target and the existing maze to use walls from.
-    pub fn with_walls(target: Target, maze: Maze) -> Self {
-        with_walls_fn(target, |x, y| maze.segment(x, y).walls)
+    /// Creates a new maze with the specified goal and the existing maze to use walls from.
+    pub fn with_walls(goal: Goal<W, H>, maze: Maze<W, H>) -> Self {
+        with_walls_fn(goal, |x, y| maze.segment(x, y).walls)
+    }
+
+    /// Returns the goal of this maze.
+    pub fn goal(&self) -> &Goal<W, H> {
+        &self.goal
     }
 
     /// Returns the segment at `x, y`.
     pub fn segment(&self, x: u8, y: u8) -> Segment {
-        self.segments[xy_to_index(x, y)]
+        self.segments.as_flattened()[xy_to_index::<W>(x, y)]
+    }
+
+    /// Returns the segment at `x, y`, or [Error::OutOfBounds] if it is outside this maze.
+    pub fn try_segment(&self, x: u8, y: u8) -> Result<Segment, Error> {
+        self.check_bounds(x, y)?;
+        Ok(self.segment(x, y))
+    }
+
+    /// Returns [Error::OutOfBounds] if `x, y` is outside this maze.
+    fn check_bounds(&self, x: u8, y: u8) -> Result<(), Error> {
+        if (x as usize) < W && (y as usize) < H {
+            Ok(())
+        } else {
+            Err(Error::OutOfBounds { pos: Vecu { x, y } })
+        }
     }
 
     /// Returns the segment at `x, y`.
@@ -81,30 +112,91 @@ impl Maze {
 
     /// Adds a wall at the specified direction.
     fn _add_wall(&mut self, x: u8, y: u8, direction: Relative) {
-        let i = xy_to_index(x, y);
-        let mut existing = self.segments[i];
-        existing.walls[direction as usize] = true;
-        self.segments[i] = existing;
-    }
-
-    /// Updates the walls of the segment at `x, y` to the specified array.
-    pub fn update_walls(&mut self, x: u8, y: u8, walls: [bool; 4]) {
-        let i = xy_to_index(x, y);
-        let mut existing = self.segments[i];
-
-        for (i, val) in existing.walls.iter().enumerate() {
-            assert!(
-                !(*val) || walls[i],
-                "Walls can only be updated from false to true"
-            )
+        let segments = self.segments.as_flattened_mut();
+        let i = xy_to_index::<W>(x, y);
+        let mut existing = segments[i];
+        existing.walls[direction as usize] = Wall::Present;
+        segments[i] = existing;
+    }
+
+    /// Updates the walls of the segment at `x, y` with the observed walls.
+    ///
+    /// Observations may be partial: sides that are [Wall::Unknown] keep their current state.
+    /// Plain `bool`s are accepted as well, where `true` is a present wall and `false` an open one.
+    ///
+    /// When a new wall is found to be present, the distances are recomputed
+    /// using [floodfill::flood].
+    ///
+    /// ### Panics
+    ///
+    /// Panics if `x, y` is outside this maze, or if a wall that is already known is observed to
+    /// be different. Use [Maze::try_update_walls] to handle these instead.
+    pub fn update_walls<T: Into<Wall>>(&mut self, x: u8, y: u8, walls: [T; 4]) {
+        if let Err(e) = self.try_update_walls(x, y, walls) {
+            panic!("Failed to update walls: {}", e);
+        }
+    }
+
+    /// Updates the walls of the segment at `x, y` with the observed walls like
+    /// [Maze::update_walls].
+    ///
+    /// ### Returns
+    ///
+    /// - [Error::OutOfBounds] - `x, y` is outside this maze.
+    /// - [Error::ConflictingWall] - A wall that is already known is observed to be different,
+    ///   for example due to a faulty sensor reading. The maze is left unchanged.
+    pub fn try_update_walls<T: Into<Wall>>(
+        &mut self,
+        x: u8,
+        y: u8,
+        walls: [T; 4],
+    ) -> Result<(), Error> {
+        if self.try_set_walls(x, y, walls)? {
+            floodfill::flood(self);
         }
+        Ok(())
+    }
 
-        existing.walls = walls;
-        self.segments[i] = existing;
+    /// Updates the walls of the segment at `x, y` like [Maze::update_walls], without
+    /// recomputing the distances. Returns whether a new wall was found to be present.
+    pub(crate) fn set_walls<T: Into<Wall>>(&mut self, x: u8, y: u8, walls: [T; 4]) -> bool {
+        self.try_set_walls(x, y, walls)
+            .unwrap_or_else(|e| panic!("Failed to update walls: {}", e))
+    }
+
+    /// Updates the walls of the segment at `x, y` like [Maze::try_update_walls], without
+    /// recomputing the distances. Returns whether a new wall was found to be present.
+    fn try_set_walls<T: Into<Wall>>(&mut self, x: u8, y: u8, walls: [T; 4]) -> Result<bool, Error> {
+        self.check_bounds(x, y)?;
+
+        let i = xy_to_index::<W>(x, y);
+        let mut existing = self.segments.as_flattened()[i];
+        let walls = walls.map(Into::into);
+        let mut added = false;
+
+        // check every wall before changing any, such that a conflict leaves the maze unchanged
+        for (i, dir) in Relative::iter().enumerate() {
+            if walls[i].is_known() && existing.walls[i].is_known() && existing.walls[i] != walls[i]
+            {
+                return Err(Error::ConflictingWall {
+                    pos: existing.pos(),
+                    relative: *dir,
+                });
+            }
+        }
+
+        for (i, wall) in walls.iter().enumerate() {
+            if wall.is_known() {
+                added |= !existing.walls[i].is_known() && wall.is_present();
+                existing.walls[i] = *wall;
+            }
+        }
+
+        self.segments.as_flattened_mut()[i] = existing;
 
         // update neighbouring segments' walls
         for (j, dir) in Relative::iter().enumerate() {
-            if !walls[j] {
+            if !walls[j].is_known() {
                 continue;
             }
 
@@ -114,17 +206,105 @@ impl Maze {
             }
 
             let mut relative = relative.unwrap();
-            relative.walls[dir.opposite() as usize] = true;
-            self.segments[pos_to_index(relative.pos())] = relative;
+            relative.walls[dir.opposite() as usize] = walls[j];
+            self.segments.as_flattened_mut()[pos_to_index::<W>(relative.pos())] = relative;
+        }
+
+        Ok(added)
+    }
+
+    /// Retracts the wall on side `relative` of the segment at `x, y`, such that it is unknown
+    /// again, and recomputes the distances using [floodfill::flood].
+    ///
+    /// ### Description
+    ///
+    /// Unlike [Maze::update_walls], this may change walls that are already known. It is meant
+    /// for walls that later observations have disproven, such as a misread sensor.
+    ///
+    /// ### Returns
+    ///
+    /// - [Error::OutOfBounds] - `x, y` is outside this maze.
+    pub fn retract_wall(&mut self, x: u8, y: u8, relative: Relative) -> Result<(), Error> {
+        self.check_bounds(x, y)?;
+        self.replace_wall(x, y, relative, Wall::Unknown);
+        floodfill::flood(self);
+        Ok(())
+    }
+
+    /// Sets the wall on side `relative` of the segment at `x, y` and of its neighbour to `wall`,
+    /// even if it is already known, without recomputing the distances.
+    pub(crate) fn replace_wall(&mut self, x: u8, y: u8, relative: Relative, wall: Wall) {
+        let i = xy_to_index::<W>(x, y);
+        self.segments.as_flattened_mut()[i].walls[relative as usize] = wall;
+
+        let existing = self.segments.as_flattened()[i];
+        if let Some(mut neighbour) = existing.relative(self, &relative) {
+            neighbour.walls[relative.opposite() as usize] = wall;
+            self.segments.as_flattened_mut()[pos_to_index::<W>(neighbour.pos())] = neighbour;
+        }
+    }
+
+    /// Whether every wall of the segment at `x, y` is known.
+    /// Walls on the border of the maze are always considered known.
+    pub fn is_known(&self, x: u8, y: u8) -> bool {
+        let segment = self.segment(x, y);
+
+        Relative::iter()
+            .enumerate()
+            .all(|(i, dir)| segment.walls[i].is_known() || segment.relative(self, dir).is_none())
+    }
+
+    /// Returns the length of the shortest route from `from` to the nearest segment of the goal.
+    /// Unknown walls are treated according to `assumption`.
+    /// Returns `None` if the goal cannot be reached.
+    pub fn shortest_route(&self, from: Vecu, assumption: Assumption) -> Option<u16> {
+        let mut lengths: Grid<u16, W, H> = Grid::new(u16::MAX);
+        let mut to_explore: Queue<W, H> = Queue::new();
+
+        lengths[from] = 0;
+        to_explore.push_back(from);
+
+        while let Some(pos) = to_explore.pop_front() {
+            let current = self.segment_vec(pos);
+            let length = lengths[pos];
+            if self.goal.contains(pos) {
+                return Some(length);
+            }
+
+            for (i, dir) in Relative::iter().enumerate() {
+                if !current.walls[i].is_passable(assumption) {
+                    continue;
+                }
+
+                if let Some(relative) = current.relative(self, dir) {
+                    let pos = relative.pos();
+                    if lengths[pos] == u16::MAX {
+                        lengths[pos] = length + 1;
+                        to_explore.push_back(pos);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Whether the shortest route from `from` to the goal over walls known to be open is
+    /// provably optimal, i.e. no unknown wall could turn out open and yield a shorter route.
+    pub fn is_route_optimal(&self, from: Vecu) -> bool {
+        match self.shortest_route(from, Assumption::Pessimistic) {
+            Some(known) => self.shortest_route(from, Assumption::Optimistic) == Some(known),
+            None => false,
         }
     }
 
     /// Updates the distance of the segment at `x, y` to the specified value.
-    pub fn update_distance(&mut self, x: u8, y: u8, distance: u8) {
-        let i = (x + y * MAZE_WIDTH_U8) as usize;
-        let existing = self.segments[i];
+    pub fn update_distance(&mut self, x: u8, y: u8, distance: u16) {
+        let segments = self.segments.as_flattened_mut();
+        let i = xy_to_index::<W>(x, y);
+        let existing = segments[i];
 
-        self.segments[i] = Segment {
+        segments[i] = Segment {
             pos: existing.pos,
             distance,
             walls: existing.walls,
@@ -132,16 +312,16 @@ impl Maze {
     }
 }
 
-impl Default for Maze {
+impl<const W: usize, const H: usize> Default for Maze<W, H> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl core::fmt::Debug for Maze {
+impl<const W: usize, const H: usize> core::fmt::Debug for Maze<W, H> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        for y in 0..MAZE_HEIGHT_U8 {
-            for x in 0..MAZE_WIDTH_U8 {
+        for y in 0..H as u8 {
+            for x in 0..W as u8 {
                 let distance = self.segment(x, y).distance;
                 if distance < 10 {
                     write!(f, "{:?}  ", distance)?;
@@ -157,7 +337,7 @@ impl core::fmt::Debug for Maze {
 }
 
 /// The relative direction.
-#[derive(PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum Relative {
     North,
     East,
@@ -186,14 +366,98 @@ impl Relative {
             Relative::West => Relative::East,
         }
     }
+
+    /// Returns the direction to the left of this direction.
+    pub fn left(&self) -> Relative {
+        match self {
+            Relative::North => Relative::West,
+            Relative::East => Relative::North,
+            Relative::South => Relative::East,
+            Relative::West => Relative::South,
+        }
+    }
+
+    /// Returns the direction to the right of this direction.
+    pub fn right(&self) -> Relative {
+        self.left().opposite()
+    }
+
+    /// Returns the direction from `from` to `to`, or `None` if they are not neighbours.
+    pub fn between(from: Vecu, to: Vecu) -> Option<Relative> {
+        match (to.x as i16 - from.x as i16, to.y as i16 - from.y as i16) {
+            (0, -1) => Some(Relative::North),
+            (1, 0) => Some(Relative::East),
+            (0, 1) => Some(Relative::South),
+            (-1, 0) => Some(Relative::West),
+            _ => None,
+        }
+    }
+}
+
+/// The knowledge about a single wall of a segment.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Wall {
+    /// The wall has not been observed yet.
+    #[default]
+    Unknown,
+    /// The wall has been observed to be absent.
+    Open,
+    /// The wall has been observed to be present.
+    Present,
+}
+
+impl Wall {
+    /// Whether this wall has been observed.
+    pub fn is_known(&self) -> bool {
+        *self != Wall::Unknown
+    }
+
+    /// Whether this wall has been observed to be present.
+    pub fn is_present(&self) -> bool {
+        *self == Wall::Present
+    }
+
+    /// Whether this wall may be passed, treating an unknown wall according to `assumption`.
+    pub fn is_passable(&self, assumption: Assumption) -> bool {
+        match self {
+            Wall::Unknown => assumption == Assumption::Optimistic,
+            Wall::Open => true,
+            Wall::Present => false,
+        }
+    }
+}
+
+impl From<bool> for Wall {
+    fn from(present: bool) -> Self {
+        if present {
+            Wall::Present
+        } else {
+            Wall::Open
+        }
+    }
+}
+
+impl From<Option<bool>> for Wall {
+    fn from(present: Option<bool>) -> Self {
+        present.map_or(Wall::Unknown, Wall::from)
+    }
+}
+
+/// How unknown walls are treated when planning.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Assumption {
+    /// Unknown walls are assumed to be open.
+    Optimistic,
+    /// Unknown walls are assumed to be present.
+    Pessimistic,
 }
 
 /// Represents a point on the grid.
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Segment {
     pos: Vecu,
-    pub distance: u8,
-    pub walls: [bool; 4],
+    pub distance: u16,
+    pub walls: [Wall; 4],
 }
 
 impl Segment {
@@ -201,30 +465,39 @@ impl Segment {
     pub fn new() -> Self {
         Segment {
             pos: Vecu::new(),
-            distance: u8::MAX,
-            walls: [false, false, false, false],
+            distance: u16::MAX,
+            walls: [Wall::Unknown; 4],
+        }
+    }
+
+    /// Creates a new Segment with the specified walls and an unknown distance.
+    fn with_walls(pos: Vecu, walls: [Wall; 4]) -> Self {
+        Segment {
+            pos,
+            distance: u16::MAX,
+            walls,
         }
     }
 
     /// Creates a new default Segment.
-    pub fn with_pos(pos: Vecu, distance: u8) -> Self {
+    pub fn with_pos(pos: Vecu, distance: u16) -> Self {
         Segment {
             pos,
             distance,
-            walls: [false, false, false, false],
+            walls: [Wall::Unknown; 4],
         }
     }
 
     /// Whether this segment is a dead end.
     /// A dead end is defined as a segment with 3 walls and 1 entrance.
     pub fn is_dead_end(&self) -> bool {
-        self.walls.iter().filter(|it| **it).count() == 3
+        self.walls.iter().filter(|it| it.is_present()).count() == 3
     }
 
     /// Whether this segment is straight.
     /// A segment is straight when there are 2 walls and 1 exit and 1 entrance.
     pub fn is_straight(&self) -> bool {
-        self.walls.iter().filter(|it| **it).count() == 2
+        self.walls.iter().filter(|it| it.is_present()).count() == 2
     }
 
     /// Returns the position of this segment.
@@ -238,14 +511,18 @@ impl Segment {
     ///
     /// - `maze` - A maze ref.
     /// - `relative` - The direction.
-    pub fn relative(&self, maze: &Maze, relative: &Relative) -> Option<Segment> {
+    pub fn relative<const W: usize, const H: usize>(
+        &self,
+        maze: &Maze<W, H>,
+        relative: &Relative,
+    ) -> Option<Segment> {
         let (x, y) = (self.pos.x, self.pos.y);
 
         match *relative {
             Relative::North if y > 0 => Some(maze.segment(x, y - 1)),
-            Relative::South if y + 1 < MAZE_HEIGHT_U8 => Some(maze.segment(x, y + 1)),
+            Relative::South if (y as usize) + 1 < H => Some(maze.segment(x, y + 1)),
             Relative::West if x > 0 => Some(maze.segment(x - 1, y)),
-            Relative::East if x + 1 < MAZE_WIDTH_U8 => Some(maze.segment(x + 1, y)),
+            Relative::East if (x as usize) + 1 < W => Some(maze.segment(x + 1, y)),
             _ => None,
         }
     }
@@ -259,12 +536,16 @@ impl Default for Segment {
 
 #[cfg(test)]
 mod tests {
-    use crate::maze::Maze;
+    use crate::error::Error;
+    use crate::goal::Goal;
+    use crate::maze::Wall::{Open, Present, Unknown};
+    use crate::maze::{Assumption, ClassicMaze, Maze, Relative};
+    use crate::vec::Vecu;
     use std::panic::{catch_unwind, AssertUnwindSafe};
 
     #[test]
     fn test_wall_update_panics() {
-        let mut maze = Maze::new();
+        let mut maze = ClassicMaze::new();
         maze.update_walls(0, 0, [false, true, false, false]);
         assert!(catch_unwind(AssertUnwindSafe(|| maze.update_walls(
             0,
@@ -274,24 +555,161 @@ mod tests {
         .is_err())
     }
 
+    #[test]
+    fn try_update_walls() {
+        let mut maze: Maze<5, 5> = Maze::new();
+        maze.update_walls(0, 0, [None, Some(true), None, None]);
+
+        assert_eq!(
+            Err(Error::ConflictingWall {
+                pos: Vecu { x: 0, y: 0 },
+                relative: Relative::East
+            }),
+            maze.try_update_walls(0, 0, [true, false, true, true])
+        );
+        // a conflicting observation does not change any wall
+        assert_eq!(
+            [Unknown, Present, Unknown, Unknown],
+            maze.segment(0, 0).walls
+        );
+
+        assert_eq!(
+            Err(Error::OutOfBounds {
+                pos: Vecu { x: 5, y: 0 }
+            }),
+            maze.try_update_walls(5, 0, [true; 4])
+        );
+        assert!(maze.try_segment(0, 5).is_err());
+        assert_eq!(
+            Ok(()),
+            maze.try_update_walls(0, 0, [true, true, false, true])
+        );
+    }
+
     #[test]
     fn test_wall_update() {
-        let mut maze = Maze::new();
+        let mut maze = ClassicMaze::new();
         maze.update_walls(0, 0, [false, true, false, false]);
 
-        assert_eq!([false, true, false, false], maze.segment(0, 0).walls);
-        assert_eq!([false, false, false, true], maze.segment(1, 0).walls);
+        assert_eq!([Open, Present, Open, Open], maze.segment(0, 0).walls);
+        assert_eq!(
+            [Unknown, Unknown, Unknown, Present],
+            maze.segment(1, 0).walls
+        );
+        assert_eq!([Open, Unknown, Unknown, Unknown], maze.segment(0, 1).walls);
 
-        let mut maze = Maze::new();
+        let mut maze = ClassicMaze::new();
         maze.update_walls(0, 0, [false, false, true, false]);
 
-        assert_eq!([false, false, true, false], maze.segment(0, 0).walls);
-        assert_eq!([true, false, false, false], maze.segment(0, 1).walls);
+        assert_eq!([Open, Open, Present, Open], maze.segment(0, 0).walls);
+        assert_eq!(
+            [Present, Unknown, Unknown, Unknown],
+            maze.segment(0, 1).walls
+        );
 
-        let mut maze = Maze::new();
+        let mut maze = ClassicMaze::new();
         maze.update_walls(1, 0, [false, false, false, true]);
 
-        assert_eq!([false, false, false, true], maze.segment(1, 0).walls);
-        assert_eq!([false, true, false, false], maze.segment(0, 0).walls);
+        assert_eq!([Open, Open, Open, Present], maze.segment(1, 0).walls);
+        assert_eq!(
+            [Unknown, Present, Unknown, Unknown],
+            maze.segment(0, 0).walls
+        );
+    }
+
+    #[test]
+    fn test_partial_wall_update() {
+        let mut maze = ClassicMaze::new();
+        maze.update_walls(1, 1, [Unknown, Present, Unknown, Unknown]);
+        maze.update_walls(1, 1, [None, None, Some(false), None]);
+
+        assert_eq!([Unknown, Present, Open, Unknown], maze.segment(1, 1).walls);
+        assert!(!maze.is_known(1, 1));
+
+        maze.update_walls(1, 1, [Open, Present, Unknown, Present]);
+        assert!(maze.is_known(1, 1));
+        assert!(!maze.is_known(1, 0));
+
+        // walls on the border of the maze are always known
+        maze.update_walls(0, 0, [Unknown, Open, Present, Unknown]);
+        assert!(maze.is_known(0, 0));
+    }
+
+    #[test]
+    fn test_goal() {
+        let maze: Maze<32, 32> = Maze::with_goal(Goal::centered(4));
+
+        assert_eq!(0, maze.segment(14, 17).distance);
+        assert_eq!(28, maze.segment(0, 0).distance);
+        assert_eq!(28, maze.segment(31, 31).distance);
+
+        let maze: Maze<5, 5> =
+            Maze::with_goal(Goal::cells(&[Vecu { x: 4, y: 0 }, Vecu { x: 0, y: 4 }]));
+
+        assert_eq!(4, maze.segment(0, 0).distance);
+        assert_eq!(4, maze.segment(2, 2).distance);
+        assert_eq!(1, maze.segment(3, 0).distance);
+        assert_eq!(
+            Some(4),
+            maze.shortest_route(Vecu::new(), Assumption::Optimistic)
+        );
+    }
+
+    #[test]
+    fn test_route_optimal() {
+        let mut maze: Maze<3, 3> = Maze::new();
+        let origin = Vecu::new();
+
+        assert_eq!(Some(2), maze.shortest_route(origin, Assumption::Optimistic));
+        assert_eq!(None, maze.shortest_route(origin, Assumption::Pessimistic));
+        assert!(!maze.is_route_optimal(origin));
+
+        maze.update_walls(0, 0, [true, false, true, true]);
+        maze.update_walls(1, 0, [true, true, false, false]);
+        assert!(maze.is_route_optimal(origin));
+
+        let mut maze: Maze<3, 3> = Maze::new();
+        maze.update_walls(0, 0, [true, true, false, true]);
+        maze.update_walls(0, 1, [false, true, false, true]);
+        maze.update_walls(0, 2, [false, false, true, true]);
+        maze.update_walls(1, 2, [Unknown, Open, Present, Unknown]);
+        maze.update_walls(2, 2, [false, true, true, false]);
+        maze.update_walls(2, 1, [Unknown, Present, Open, Open]);
+
+        assert_eq!(
+            Some(6),
+            maze.shortest_route(origin, Assumption::Pessimistic)
+        );
+        assert_eq!(Some(4), maze.shortest_route(origin, Assumption::Optimistic));
+        assert!(!maze.is_route_optimal(origin));
+
+        maze.update_walls(1, 2, [Present, Unknown, Unknown, Unknown]);
+        assert_eq!(Some(6), maze.shortest_route(origin, Assumption::Optimistic));
+        assert!(maze.is_route_optimal(origin));
+    }
+
+    #[test]
+    fn test_dimensions() {
+        let maze: Maze<5, 5> = Maze::new();
+
+        assert_eq!(0, maze.segment(2, 2).distance);
+        assert_eq!(4, maze.segment(0, 0).distance);
+        assert_eq!(4, maze.segment(4, 4).distance);
+
+        let mut maze: Maze<32, 8> = Maze::new();
+
+        assert_eq!(0, maze.segment(15, 3).distance);
+        assert_eq!(0, maze.segment(16, 4).distance);
+        assert_eq!(18, maze.segment(31, 7).distance);
+
+        maze.update_walls(31, 7, [true, false, false, true]);
+        assert_eq!(
+            [Unknown, Unknown, Present, Unknown],
+            maze.segment(31, 6).walls
+        );
+        assert_eq!(
+            [Unknown, Present, Unknown, Unknown],
+            maze.segment(30, 7).walls
+        );
     }
 }
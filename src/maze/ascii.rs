@@ -0,0 +1,402 @@
+use crate::floodfill;
+use crate::maze::{Maze, Relative, Wall};
+
+/// The character used for posts when writing a maze.
+const POST: char = '+';
+
+/// The width of a segment in characters, excluding posts.
+const SEGMENT_WIDTH: usize = 3;
+
+/// An error that occurred while parsing an ASCII maze drawing.
+/// Lines and columns start at 1.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+/// The reason an ASCII maze drawing could not be parsed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The drawing has fewer lines than the maze requires.
+    TooFewLines,
+    /// The drawing has more non-empty lines than the maze requires.
+    TooManyLines,
+    /// A line is longer than the maze requires.
+    LineTooLong,
+    /// A post is not `+` or `o`.
+    InvalidPost(char),
+    /// A horizontal wall is not `---` or empty.
+    InvalidHorizontalWall(char),
+    /// A vertical wall is not `|` or empty.
+    InvalidVerticalWall(char),
+}
+
+impl core::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+
+        match self.kind {
+            ParseErrorKind::TooFewLines => write!(f, "expected more lines"),
+            ParseErrorKind::TooManyLines => write!(f, "unexpected line after the maze"),
+            ParseErrorKind::LineTooLong => write!(f, "line is too long"),
+            ParseErrorKind::InvalidPost(c) => write!(f, "expected a post, found {:?}", c),
+            ParseErrorKind::InvalidHorizontalWall(c) => {
+                write!(f, "expected `---` or spaces, found {:?}", c)
+            }
+            ParseErrorKind::InvalidVerticalWall(c) => {
+                write!(f, "expected `|` or a space, found {:?}", c)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+/// The characters of a line of a drawing, read from left to right.
+struct Columns<'a> {
+    chars: core::str::Chars<'a>,
+    /// The column of the next character of `chars`.
+    column: usize,
+}
+
+impl<'a> Columns<'a> {
+    fn new(line: &'a str) -> Self {
+        Columns {
+            chars: line.chars(),
+            column: 0,
+        }
+    }
+
+    /// Returns the character at `column` (0-based), where missing characters are spaces.
+    /// Columns must be read from left to right, and at most once.
+    fn at(&mut self, column: usize) -> char {
+        debug_assert!(
+            column >= self.column,
+            "Columns must be read from left to right"
+        );
+
+        let c = self.chars.nth(column - self.column).unwrap_or(' ');
+        self.column = column + 1;
+        c
+    }
+}
+
+/// Parses the post at `column` (0-based) on line `number`.
+fn parse_post(line: &mut Columns, number: usize, column: usize) -> Result<(), ParseError> {
+    match line.at(column) {
+        'o' | '+' => Ok(()),
+        c => Err(ParseError {
+            line: number,
+            column: column + 1,
+            kind: ParseErrorKind::InvalidPost(c),
+        }),
+    }
+}
+
+/// Parses a line of posts and horizontal walls, storing whether each wall is present.
+fn parse_post_line<const W: usize>(
+    line: &str,
+    number: usize,
+    walls: &mut [bool; W],
+) -> Result<(), ParseError> {
+    let mut line = Columns::new(line);
+
+    for (x, wall) in walls.iter_mut().enumerate() {
+        let post = x * (SEGMENT_WIDTH + 1);
+        parse_post(&mut line, number, post)?;
+
+        let first = line.at(post + 1);
+        *wall = first == '-';
+        for column in post + 1..post + 1 + SEGMENT_WIDTH {
+            let c = if column == post + 1 {
+                first
+            } else {
+                line.at(column)
+            };
+
+            if c != if *wall { '-' } else { ' ' } {
+                return Err(ParseError {
+                    line: number,
+                    column: column + 1,
+                    kind: ParseErrorKind::InvalidHorizontalWall(c),
+                });
+            }
+        }
+    }
+
+    parse_post(&mut line, number, W * (SEGMENT_WIDTH + 1))
+}
+
+/// Parses the vertical wall at `column` (0-based) on line `number`, returning whether it is present.
+fn parse_vertical_wall(
+    line: &mut Columns,
+    number: usize,
+    column: usize,
+) -> Result<bool, ParseError> {
+    match line.at(column) {
+        '|' => Ok(true),
+        ' ' => Ok(false),
+        c => Err(ParseError {
+            line: number,
+            column: column + 1,
+            kind: ParseErrorKind::InvalidVerticalWall(c),
+        }),
+    }
+}
+
+impl<const W: usize, const H: usize> Maze<W, H> {
+    /// Parses a maze from the community ASCII format, where posts are drawn as `+` or `o`,
+    /// horizontal walls as `---` and vertical walls as `|`.
+    ///
+    /// ```text
+    /// +---+---+
+    /// |       |
+    /// +   +---+
+    /// |   |   |
+    /// +---+---+
+    /// ```
+    ///
+    /// Following the community convention, the start is in the bottom-left corner.
+    /// The bottom row of the drawing therefore has `y = 0`, and the top of the drawing
+    /// is [Relative::South]. Every wall in the drawing is known.
+    /// Anything inside a segment, such as a start or goal marker, is ignored.
+    pub fn from_ascii(ascii: &str) -> Result<Self, ParseError> {
+        let width = W * (SEGMENT_WIDTH + 1) + 1;
+        let mut count = 0;
+
+        for (i, line) in ascii.lines().enumerate() {
+            let trimmed = line.trim_end().chars().count();
+            count = i + 1;
+
+            if i > 2 * H && trimmed > 0 {
+                return Err(ParseError {
+                    line: i + 1,
+                    column: 1,
+                    kind: ParseErrorKind::TooManyLines,
+                });
+            }
+
+            if trimmed > width {
+                return Err(ParseError {
+                    line: i + 1,
+                    column: width + 1,
+                    kind: ParseErrorKind::LineTooLong,
+                });
+            }
+        }
+
+        if count < 2 * H + 1 {
+            return Err(ParseError {
+                line: count + 1,
+                column: 1,
+                kind: ParseErrorKind::TooFewLines,
+            });
+        }
+
+        // `horizontal[k]` contains the walls on post line `k`, counted from the top.
+        let mut horizontal = [[false; W]; H];
+        let mut bottom = [false; W];
+        // `vertical[k]` contains the walls on segment line `k`, counted from the top.
+        let mut vertical = [[false; W]; H];
+        let mut right = [false; H];
+        let mut lines = ascii.lines();
+
+        for k in 0..H {
+            let post_line = lines.next().expect("Lines were counted");
+            parse_post_line(post_line, 2 * k + 1, &mut horizontal[k])?;
+
+            let mut line = Columns::new(lines.next().expect("Lines were counted"));
+            for (x, wall) in vertical[k].iter_mut().enumerate() {
+                *wall = parse_vertical_wall(&mut line, 2 * k + 2, x * (SEGMENT_WIDTH + 1))?;
+            }
+            right[k] = parse_vertical_wall(&mut line, 2 * k + 2, W * (SEGMENT_WIDTH + 1))?;
+        }
+        let bottom_line = lines.next().expect("Lines were counted");
+        parse_post_line(bottom_line, 2 * H + 1, &mut bottom)?;
+
+        let mut maze = Maze::new();
+        for y in 0..H {
+            let k = H - 1 - y;
+            for x in 0..W {
+                let mut walls = [Wall::Unknown; 4];
+                walls[Relative::North as usize] = if k + 1 < H {
+                    horizontal[k + 1][x]
+                } else {
+                    bottom[x]
+                }
+                .into();
+                walls[Relative::South as usize] = horizontal[k][x].into();
+                walls[Relative::West as usize] = vertical[k][x].into();
+                walls[Relative::East as usize] = if x + 1 < W {
+                    vertical[k][x + 1]
+                } else {
+                    right[k]
+                }
+                .into();
+
+                maze.set_walls(x as u8, y as u8, walls);
+            }
+        }
+
+        floodfill::flood(&mut maze);
+        Ok(maze)
+    }
+
+    /// Writes this maze in the community ASCII format, as read by [Maze::from_ascii].
+    /// Walls that are not known to be present are written as open.
+    pub fn write_ascii<F: core::fmt::Write>(&self, f: &mut F) -> core::fmt::Result {
+        for k in 0..=H {
+            // the post line above drawing row `k`, which is the south side of `y`
+            for x in 0..W as u8 {
+                let present = if k == 0 {
+                    self.segment(x, (H - 1) as u8).walls[Relative::South as usize]
+                } else {
+                    self.segment(x, (H - k) as u8).walls[Relative::North as usize]
+                }
+                .is_present();
+
+                f.write_char(POST)?;
+                f.write_str(if present { "---" } else { "   " })?;
+            }
+            f.write_char(POST)?;
+            f.write_char('\n')?;
+
+            if k == H {
+                break;
+            }
+
+            let y = (H - 1 - k) as u8;
+            for x in 0..W as u8 {
+                let present = self.segment(x, y).walls[Relative::West as usize].is_present();
+                f.write_char(if present { '|' } else { ' ' })?;
+                f.write_str("   ")?;
+            }
+            let present =
+                self.segment((W - 1) as u8, y).walls[Relative::East as usize].is_present();
+            f.write_char(if present { '|' } else { ' ' })?;
+            f.write_char('\n')?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns this maze in the community ASCII format, as read by [Maze::from_ascii].
+    /// Walls that are not known to be present are written as open.
+    #[cfg(any(feature = "std", test))]
+    pub fn to_ascii(&self) -> String {
+        let mut ascii = String::with_capacity((2 * H + 1) * (W * (SEGMENT_WIDTH + 1) + 2));
+        self.write_ascii(&mut ascii)
+            .expect("Writing to a String cannot fail");
+        ascii
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::maze::ascii::{ParseError, ParseErrorKind};
+    use crate::maze::Maze;
+    use crate::maze::Wall::{Open, Present};
+
+    const SMALL: &str = "\
++---+---+---+
+|           |
++   +---+   +
+|   |   |   |
++   +   +   +
+|   |       |
++---+---+---+
+";
+
+    #[test]
+    fn parse() {
+        let maze: Maze<3, 3> = Maze::from_ascii(SMALL).unwrap();
+
+        // the bottom-left corner is the origin, and the top of the drawing is south
+        assert_eq!([Present, Present, Open, Present], maze.segment(0, 0).walls);
+        assert_eq!([Present, Open, Open, Present], maze.segment(1, 0).walls);
+        assert_eq!([Present, Present, Open, Open], maze.segment(2, 0).walls);
+        assert_eq!([Open, Present, Present, Present], maze.segment(1, 1).walls);
+        assert_eq!([Open, Open, Present, Present], maze.segment(0, 2).walls);
+        assert!((0..3).all(|x| (0..3).all(|y| maze.is_known(x, y))));
+    }
+
+    #[test]
+    fn round_trip() {
+        let maze: Maze<3, 3> = Maze::from_ascii(SMALL).unwrap();
+        assert_eq!(SMALL, maze.to_ascii());
+
+        let mut maze: Maze<4, 2> = Maze::new();
+        maze.update_walls(0, 0, [true, false, false, true]);
+        maze.update_walls(3, 1, [false, true, true, true]);
+
+        let parsed: Maze<4, 2> = Maze::from_ascii(&maze.to_ascii()).unwrap();
+        assert_eq!(maze.to_ascii(), parsed.to_ascii());
+    }
+
+    #[test]
+    fn parse_lenient() {
+        // `o` posts, segment contents and trimmed lines are accepted
+        let ascii = "o---o---o\n| S     |\no   o---o\n|     G\no---o---o\n\n";
+        let maze: Maze<2, 2> = Maze::from_ascii(ascii).unwrap();
+
+        assert_eq!(Open, maze.segment(1, 0).walls[1]);
+        assert_eq!(Present, maze.segment(1, 1).walls[2]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |ascii: &str| Maze::<2, 2>::from_ascii(ascii).unwrap_err();
+
+        assert_eq!(
+            ParseError {
+                line: 4,
+                column: 1,
+                kind: ParseErrorKind::TooFewLines
+            },
+            error("o---o---o\n|       |\no   o   o\n")
+        );
+        assert_eq!(
+            ParseError {
+                line: 3,
+                column: 5,
+                kind: ParseErrorKind::InvalidPost('-')
+            },
+            error("o---o---o\n|       |\no   ----o\n|       |\no---o---o\n")
+        );
+        assert_eq!(
+            ParseError {
+                line: 1,
+                column: 3,
+                kind: ParseErrorKind::InvalidHorizontalWall(' ')
+            },
+            error("o- -o---o\n|       |\no   o   o\n|       |\no---o---o\n")
+        );
+        assert_eq!(
+            ParseError {
+                line: 4,
+                column: 9,
+                kind: ParseErrorKind::InvalidVerticalWall('#')
+            },
+            error("o---o---o\n|       |\no   o   o\n|       #\no---o---o\n")
+        );
+        assert_eq!(
+            ParseError {
+                line: 2,
+                column: 10,
+                kind: ParseErrorKind::LineTooLong
+            },
+            error("o---o---o\n|       | x\no   o   o\n|       |\no---o---o\n")
+        );
+        assert_eq!(
+            ParseError {
+                line: 6,
+                column: 1,
+                kind: ParseErrorKind::TooManyLines
+            },
+            error("o---o---o\n|       |\no   o   o\n|       |\no---o---o\no\n")
+        );
+    }
+}
@@ -0,0 +1,310 @@
+use crate::error::Error;
+use crate::floodfill;
+use crate::grid::Grid;
+use crate::maze::{Maze, Relative, Wall};
+use crate::vec::Vecu;
+
+/// The amount of readings that must agree more than they disagree before a wall is committed,
+/// used by [Evidence::default].
+pub const DEFAULT_THRESHOLD: u8 = 2;
+
+/// The amount of times a single wall has been seen to be present and absent.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Count {
+    pub present: u8,
+    pub absent: u8,
+}
+
+impl Count {
+    /// Counts a single reading of the wall, which was seen to be present if `present` is set.
+    ///
+    /// When the count of the reading is about to overflow, both counts are halved first. This
+    /// keeps which kind of reading outnumbers the other, such that later readings can still
+    /// retract a wall that has been seen many times.
+    pub fn add(&mut self, present: bool) {
+        let count = if present { self.present } else { self.absent };
+        if count == u8::MAX {
+            self.present /= 2;
+            self.absent /= 2;
+        }
+
+        if present {
+            self.present += 1;
+        } else {
+            self.absent += 1;
+        }
+    }
+
+    /// Returns the state of the wall, given that one kind of reading must outnumber the other
+    /// by at least `threshold` before the wall is known.
+    pub fn state(&self, threshold: u8) -> Wall {
+        let (present, absent, threshold) =
+            (self.present as u16, self.absent as u16, threshold as u16);
+
+        if present >= absent + threshold {
+            Wall::Present
+        } else if absent >= present + threshold {
+            Wall::Open
+        } else {
+            Wall::Unknown
+        }
+    }
+}
+
+/// The readings of every wall inside a maze of `W` by `H` segments, used to reconcile sensor
+/// readings that contradict each other.
+///
+/// ### Description
+///
+/// Instead of committing every reading directly to the maze, which fails on a reading that
+/// contradicts an earlier one, every reading is counted. A wall is only committed to the maze
+/// once one kind of reading outnumbers the other by the threshold. When later readings disprove
+/// a committed wall, it is retracted from the maze and the distances are recomputed, such that
+/// the pathfinder plans with the corrected walls.
+///
+/// Walls on the border of the maze are ignored, as the pathfinder never crosses them.
+///
+/// ### Implementation
+///
+/// Every inner wall is shared by two segments, so only the east and south walls of every
+/// segment are counted. The north and west walls are counted as the south and east walls of the
+/// neighbouring segments.
+pub struct Evidence<const W: usize, const H: usize> {
+    /// The readings of the east and south walls of every segment.
+    counts: Grid<[Count; 2], W, H>,
+    threshold: u8,
+}
+
+impl<const W: usize, const H: usize> Evidence<W, H> {
+    /// Creates a new instance without any readings.
+    ///
+    /// ### Arguments
+    ///
+    /// - `threshold` - The amount by which one kind of reading must outnumber the other before
+    ///   a wall is committed. A threshold of 1 commits a wall on its first reading.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if `threshold` is zero.
+    pub fn new(threshold: u8) -> Self {
+        assert!(threshold > 0, "Threshold must be at least 1");
+
+        Evidence {
+            counts: Grid::new([Count::default(); 2]),
+            threshold,
+        }
+    }
+
+    /// Returns the amount by which one kind of reading must outnumber the other.
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// Returns the segment and the index in `counts` of the wall on side `relative`
+    /// of the segment at `x, y`, or `None` if the wall is on the border.
+    fn wall(x: u8, y: u8, relative: Relative) -> Option<(Vecu, usize)> {
+        match relative {
+            Relative::North if y > 0 => Some((Vecu { x, y: y - 1 }, 1)),
+            Relative::East if (x as usize) + 1 < W => Some((Vecu { x, y }, 0)),
+            Relative::South if (y as usize) + 1 < H => Some((Vecu { x, y }, 1)),
+            Relative::West if x > 0 => Some((Vecu { x: x - 1, y }, 0)),
+            _ => None,
+        }
+    }
+
+    /// Returns the readings of the wall on side `relative` of the segment at `x, y`, or `None`
+    /// if the wall is on the border.
+    pub fn count(&self, x: u8, y: u8, relative: Relative) -> Option<Count> {
+        Self::wall(x, y, relative).map(|(pos, side)| self.counts[pos][side])
+    }
+
+    /// Counts the walls observed from the segment at `x, y`, and updates the walls in `maze`
+    /// accordingly.
+    ///
+    /// ### Description
+    ///
+    /// Sides that are [Wall::Unknown] are not counted. Every other wall is set in `maze` to the
+    /// state its readings support: present or open if one kind of reading outnumbers the other
+    /// by the threshold, and unknown otherwise. When a wall is found to be present or a present
+    /// wall is retracted, the distances are recomputed using [floodfill::flood].
+    ///
+    /// ### Arguments
+    ///
+    /// - `maze` - The known maze, which is updated with the committed walls.
+    /// - `x`, `y` - The position of the segment the walls were observed from.
+    /// - `walls` - The observed walls, in the order of [Relative].
+    ///
+    /// ### Returns
+    ///
+    /// - `Ok(true)` - A wall that was known in `maze` has been retracted or changed.
+    /// - `Ok(false)` - No known wall has been changed.
+    /// - [Error::OutOfBounds] - `x, y` is outside `maze`.
+    pub fn observe<T: Into<Wall>>(
+        &mut self,
+        maze: &mut Maze<W, H>,
+        x: u8,
+        y: u8,
+        walls: [T; 4],
+    ) -> Result<bool, Error> {
+        maze.try_segment(x, y)?;
+
+        let walls = walls.map(Into::into);
+        let mut retracted = false;
+        let mut flood = false;
+
+        for (i, dir) in Relative::iter().enumerate() {
+            let Some((pos, side)) = Self::wall(x, y, *dir) else {
+                continue;
+            };
+
+            let count = &mut self.counts[pos][side];
+            match walls[i] {
+                Wall::Present => count.add(true),
+                Wall::Open => count.add(false),
+                Wall::Unknown => continue,
+            }
+
+            let state = count.state(self.threshold);
+            let current = maze.segment(x, y).walls[i];
+            if state == current {
+                continue;
+            }
+
+            retracted |= current.is_known();
+            flood |= current.is_present() || state.is_present();
+            maze.replace_wall(x, y, *dir, state);
+        }
+
+        if flood {
+            floodfill::flood(maze);
+        }
+        Ok(retracted)
+    }
+}
+
+impl<const W: usize, const H: usize> Default for Evidence<W, H> {
+    fn default() -> Self {
+        Self::new(DEFAULT_THRESHOLD)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::floodfill::UNREACHABLE;
+    use crate::maze::evidence::{Count, Evidence};
+    use crate::maze::Wall::{Open, Present, Unknown};
+    use crate::maze::{Maze, Relative};
+    use crate::path::Path;
+    use crate::pathfinder;
+    use crate::vec::Vecu;
+
+    #[test]
+    fn count() {
+        let count = Count {
+            present: 3,
+            absent: 1,
+        };
+
+        assert_eq!(Present, count.state(2));
+        assert_eq!(Unknown, count.state(3));
+        assert_eq!(Unknown, Count::default().state(1));
+        assert_eq!(
+            Open,
+            Count {
+                present: 0,
+                absent: 1
+            }
+            .state(1)
+        );
+    }
+
+    #[test]
+    fn retract() {
+        let mut maze: Maze<5, 5> = Maze::new();
+        let mut evidence = Evidence::new(2);
+        let walls = [None, Some(true), Some(true), None];
+
+        // a single reading is not enough to commit the walls
+        assert_eq!(Ok(false), evidence.observe(&mut maze, 0, 0, walls));
+        assert_eq!(4, maze.segment(0, 0).distance);
+
+        // the origin is walled in after the second reading
+        assert_eq!(Ok(false), evidence.observe(&mut maze, 0, 0, walls));
+        assert_eq!(Present, maze.segment(0, 0).walls[Relative::East as usize]);
+        assert_eq!(Present, maze.segment(1, 0).walls[Relative::West as usize]);
+        assert_eq!(UNREACHABLE, maze.segment(0, 0).distance);
+
+        // the east wall is seen to be absent from the neighbour, which makes it undecided
+        let open = [None, None, None, Some(false)];
+        assert_eq!(Ok(true), evidence.observe(&mut maze, 1, 0, open));
+        assert_eq!(Ok(false), evidence.observe(&mut maze, 1, 0, open));
+        assert_eq!(Unknown, maze.segment(0, 0).walls[Relative::East as usize]);
+        assert_eq!(4, maze.segment(0, 0).distance);
+
+        assert_eq!(Ok(false), evidence.observe(&mut maze, 1, 0, open));
+        assert_eq!(Ok(false), evidence.observe(&mut maze, 1, 0, open));
+        assert_eq!(Open, maze.segment(0, 0).walls[Relative::East as usize]);
+        assert_eq!(
+            Some(Count {
+                present: 2,
+                absent: 4
+            }),
+            evidence.count(0, 0, Relative::East)
+        );
+
+        // the pathfinder plans through the retracted wall
+        let mut path = Path::new();
+        path.append(Vecu::new());
+        let next = pathfinder::next(&maze, &path, &mut Path::new()).unwrap_found();
+        assert_eq!(Vecu { x: 1, y: 0 }, next.pos());
+    }
+
+    #[test]
+    fn saturate() {
+        let mut count = Count {
+            present: u8::MAX,
+            absent: u8::MAX,
+        };
+        count.add(false);
+        assert_eq!(
+            Count {
+                present: 127,
+                absent: 128
+            },
+            count
+        );
+
+        let mut maze: Maze<5, 5> = Maze::new();
+        let mut evidence = Evidence::new(2);
+        let present = [None, Some(true), None, None];
+        let open = [None, Some(false), None, None];
+
+        // the wall is seen far more often than a count can hold
+        for _ in 0..1000 {
+            assert_eq!(Ok(false), evidence.observe(&mut maze, 0, 0, present));
+        }
+        assert_eq!(Present, maze.segment(0, 0).walls[Relative::East as usize]);
+
+        // later readings still retract it, and then commit it as open
+        let mut retracted = 0;
+        for _ in 0..300 {
+            if evidence.observe(&mut maze, 0, 0, open).unwrap() {
+                retracted += 1;
+            }
+        }
+        assert_eq!(1, retracted);
+        assert_eq!(Open, maze.segment(0, 0).walls[Relative::East as usize]);
+    }
+
+    #[test]
+    fn border() {
+        let mut maze: Maze<5, 5> = Maze::new();
+        let mut evidence = Evidence::new(1);
+
+        assert_eq!(None, evidence.count(0, 0, Relative::North));
+        assert_eq!(None, evidence.count(4, 0, Relative::East));
+        assert_eq!(Ok(false), evidence.observe(&mut maze, 0, 0, [true; 4]));
+        assert_eq!(Unknown, maze.segment(0, 0).walls[Relative::North as usize]);
+        assert!(evidence.observe(&mut maze, 5, 0, [true; 4]).is_err());
+    }
+}
@@ -0,0 +1,103 @@
+use crate::maze::ascii::ParseError;
+use crate::maze::maz::MazError;
+use crate::maze::{ClassicMaze, HalfSizeMaze, Maze};
+use crate::MAZE_SIZE;
+
+/// The number of segments in the half-size maze.
+const HALF_SIZE_MAZE_SIZE: usize = 32 * 32;
+
+/// An error that occurred while reading a maze file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FileError {
+    /// The file has the length of a `.maz` file, but is not a valid one.
+    Maz(MazError),
+    /// The file is not a valid classic or half-size ASCII maze.
+    /// Contains the error of parsing it as a classic maze.
+    Ascii(ParseError),
+    /// The file is neither a `.maz` file nor text.
+    NotText,
+}
+
+impl core::fmt::Display for FileError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FileError::Maz(e) => write!(f, "invalid .maz file: {}", e),
+            FileError::Ascii(e) => write!(f, "invalid ASCII maze: {}", e),
+            FileError::NotText => write!(f, "not a .maz file or ASCII maze"),
+        }
+    }
+}
+
+impl std::error::Error for FileError {}
+
+/// A maze read from a file, of which the size is only known after reading it.
+#[derive(Clone, Debug)]
+pub enum MazeFile {
+    /// A classic 16x16 maze.
+    Classic(Box<ClassicMaze>),
+    /// A half-size 32x32 maze.
+    HalfSize(Box<HalfSizeMaze>),
+}
+
+impl MazeFile {
+    /// Reads a classic or half-size maze from `bytes`.
+    ///
+    /// Files of exactly 256 or 1024 bytes are read with [Maze::from_maz].
+    /// Any other file is read with [Maze::from_ascii].
+    pub fn read(bytes: &[u8]) -> Result<Self, FileError> {
+        match bytes.len() {
+            MAZE_SIZE => {
+                return Maze::from_maz(bytes)
+                    .map(|maze| MazeFile::Classic(Box::new(maze)))
+                    .map_err(FileError::Maz)
+            }
+            HALF_SIZE_MAZE_SIZE => {
+                return Maze::from_maz(bytes)
+                    .map(|maze| MazeFile::HalfSize(Box::new(maze)))
+                    .map_err(FileError::Maz)
+            }
+            _ => {}
+        }
+
+        let ascii = core::str::from_utf8(bytes).map_err(|_| FileError::NotText)?;
+        match Maze::from_ascii(ascii) {
+            Ok(maze) => Ok(MazeFile::Classic(Box::new(maze))),
+            Err(e) => Maze::from_ascii(ascii)
+                .map(|maze| MazeFile::HalfSize(Box::new(maze)))
+                .map_err(|_| FileError::Ascii(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::maze::file::{FileError, MazeFile};
+    use crate::maze::generate::{generate, Algorithm};
+    use crate::maze::{ClassicMaze, HalfSizeMaze};
+
+    #[test]
+    fn read() {
+        let classic: ClassicMaze = generate(Algorithm::Prim, 1, 0.1);
+        let half_size: HalfSizeMaze = generate(Algorithm::Kruskal, 1, 0.1);
+
+        for bytes in [classic.to_maz(), classic.to_ascii().into_bytes()] {
+            match MazeFile::read(&bytes).unwrap() {
+                MazeFile::Classic(maze) => assert_eq!(classic.to_ascii(), maze.to_ascii()),
+                MazeFile::HalfSize(_) => panic!("Expected a classic maze"),
+            }
+        }
+
+        for bytes in [half_size.to_maz(), half_size.to_ascii().into_bytes()] {
+            match MazeFile::read(&bytes).unwrap() {
+                MazeFile::Classic(_) => panic!("Expected a half-size maze"),
+                MazeFile::HalfSize(maze) => assert_eq!(half_size.to_ascii(), maze.to_ascii()),
+            }
+        }
+
+        assert!(matches!(
+            MazeFile::read(b"+---+\n"),
+            Err(FileError::Ascii(_))
+        ));
+        assert_eq!(FileError::NotText, MazeFile::read(&[0xff; 3]).unwrap_err());
+    }
+}
@@ -0,0 +1,526 @@
+use crate::floodfill;
+use crate::goal::Goal;
+use crate::maze::{Maze, Relative, Wall};
+use crate::vec::Vecu;
+
+/// The algorithm used to carve the spanning tree of a generated maze.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Randomized depth-first search, which produces long winding corridors.
+    Backtracker,
+    /// Randomized Prim's algorithm, which produces many short dead ends.
+    Prim,
+    /// Randomized Kruskal's algorithm, which produces an unbiased mix of both.
+    Kruskal,
+}
+
+/// A small seedable pseudo-random number generator (SplitMix64).
+struct Random {
+    state: u64,
+}
+
+impl Random {
+    fn new(seed: u64) -> Self {
+        Random { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a random number in `0..bound`.
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Returns `true` with probability `p`.
+    fn chance(&mut self, p: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+}
+
+/// The walls of a maze being generated. All walls start out present.
+struct Walls<const W: usize, const H: usize> {
+    /// `east[y][x]` is the wall between `x, y` and `x + 1, y`.
+    east: [[bool; W]; H],
+    /// `south[y][x]` is the wall between `x, y` and `x, y + 1`.
+    south: [[bool; W]; H],
+}
+
+impl<const W: usize, const H: usize> Walls<W, H> {
+    /// Returns the neighbour of `pos` in direction `relative`, if it is inside the maze.
+    fn neighbour(pos: Vecu, relative: Relative) -> Option<Vecu> {
+        let (x, y) = (pos.x, pos.y);
+
+        match relative {
+            Relative::North if y > 0 => Some(Vecu { x, y: y - 1 }),
+            Relative::South if (y as usize) + 1 < H => Some(Vecu { x, y: y + 1 }),
+            Relative::West if x > 0 => Some(Vecu { x: x - 1, y }),
+            Relative::East if (x as usize) + 1 < W => Some(Vecu { x: x + 1, y }),
+            _ => None,
+        }
+    }
+
+    /// Returns the wall on side `relative` of `pos`. Walls on the border are always present.
+    fn get(&self, pos: Vecu, relative: Relative) -> bool {
+        let (x, y) = (pos.x as usize, pos.y as usize);
+
+        match relative {
+            Relative::North => y == 0 || self.south[y - 1][x],
+            Relative::South => y + 1 == H || self.south[y][x],
+            Relative::West => x == 0 || self.east[y][x - 1],
+            Relative::East => x + 1 == W || self.east[y][x],
+        }
+    }
+
+    /// Sets the wall on side `relative` of `pos`, which must not be on the border.
+    fn set(&mut self, pos: Vecu, relative: Relative, present: bool) {
+        let (x, y) = (pos.x as usize, pos.y as usize);
+
+        match relative {
+            Relative::North => self.south[y - 1][x] = present,
+            Relative::South => self.south[y][x] = present,
+            Relative::West => self.east[y][x - 1] = present,
+            Relative::East => self.east[y][x] = present,
+        }
+    }
+
+    /// Whether the post at the north-west corner of `pos` touches at least one wall.
+    /// `pos` may be one past the maze to address posts on the border.
+    fn post_has_wall(&self, pos: Vecu) -> bool {
+        if pos.x == 0 || pos.y == 0 || pos.x as usize == W || pos.y as usize == H {
+            return true;
+        }
+
+        let corner = Vecu {
+            x: pos.x - 1,
+            y: pos.y - 1,
+        };
+        self.get(corner, Relative::East)
+            || self.get(corner, Relative::South)
+            || self.get(pos, Relative::North)
+            || self.get(pos, Relative::West)
+    }
+}
+
+/// The cells that have a special role in a competition maze.
+struct Rules<const W: usize, const H: usize> {
+    goal: Goal<W, H>,
+}
+
+impl<const W: usize, const H: usize> Rules<W, H> {
+    fn new() -> Self {
+        Rules {
+            goal: Goal::center(),
+        }
+    }
+
+    /// Whether `pos` is part of the goal in the center of the maze.
+    fn is_goal(&self, pos: Vecu) -> bool {
+        self.goal.contains(pos)
+    }
+
+    /// Whether `pos` is part of the spanning tree, i.e. not the start or the goal.
+    fn is_tree(&self, pos: Vecu) -> bool {
+        pos != Vecu::new() && !self.is_goal(pos)
+    }
+}
+
+/// Generates a random maze that follows the micromouse competition rules.
+///
+/// ### Description
+///
+/// - The start at `0, 0` is walled in on three sides and only opens towards [Relative::South].
+/// - The goal in the center of the maze is open inside and has exactly one entrance.
+/// - Every post touches at least one wall, except for the post in the middle of the goal.
+///
+/// With a `loopiness` of zero, the maze is perfect: there is exactly one route between any two
+/// segments outside of the goal. Otherwise, every wall between two segments outside of the start
+/// and the goal is removed with probability `loopiness`, as long as the post rule still holds.
+///
+/// The same `algorithm`, `seed` and `loopiness` always produce the same maze.
+///
+/// ### Panics
+///
+/// Panics if either dimension is smaller than 3.
+pub fn generate<const W: usize, const H: usize>(
+    algorithm: Algorithm,
+    seed: u64,
+    loopiness: f64,
+) -> Maze<W, H> {
+    assert!(W >= 3 && H >= 3, "Generated mazes must be at least 3x3");
+
+    let rules = Rules::<W, H>::new();
+    let mut random = Random::new(seed);
+    let mut walls = Walls {
+        east: [[true; W]; H],
+        south: [[true; W]; H],
+    };
+
+    match algorithm {
+        Algorithm::Backtracker => backtracker(&rules, &mut walls, &mut random),
+        Algorithm::Prim => prim(&rules, &mut walls, &mut random),
+        Algorithm::Kruskal => kruskal(&rules, &mut walls, &mut random),
+    }
+
+    // the start only opens towards the rest of the maze
+    walls.set(Vecu::new(), Relative::South, false);
+
+    // the goal is open inside and has a single entrance
+    let mut entrances = [(Vecu::new(), Relative::North); 8];
+    let mut count = 0;
+    for pos in rules.goal.iter() {
+        for dir in Relative::iter() {
+            match Walls::<W, H>::neighbour(pos, *dir) {
+                Some(other) if rules.is_goal(other) => walls.set(pos, *dir, false),
+                Some(other) if rules.is_tree(other) => {
+                    entrances[count] = (pos, *dir);
+                    count += 1;
+                }
+                _ => {}
+            }
+        }
+    }
+    let (pos, dir) = entrances[random.below(count)];
+    walls.set(pos, dir, false);
+
+    if loopiness > 0. {
+        for x in 0..W as u8 {
+            for y in 0..H as u8 {
+                let pos = Vecu { x, y };
+                for dir in [Relative::East, Relative::South] {
+                    let other = match Walls::<W, H>::neighbour(pos, dir) {
+                        Some(other) if rules.is_tree(pos) && rules.is_tree(other) => other,
+                        _ => continue,
+                    };
+                    if !walls.get(pos, dir) || !random.chance(loopiness) {
+                        continue;
+                    }
+
+                    // the posts at both ends of the wall
+                    let posts = [other, Vecu { x: x + 1, y: y + 1 }];
+                    walls.set(pos, dir, false);
+                    if !posts.iter().all(|post| walls.post_has_wall(*post)) {
+                        walls.set(pos, dir, true);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut maze = Maze::new();
+    for x in 0..W as u8 {
+        for y in 0..H as u8 {
+            let pos = Vecu { x, y };
+            let mut known = [Wall::Unknown; 4];
+            for (i, dir) in Relative::iter().enumerate() {
+                known[i] = walls.get(pos, *dir).into();
+            }
+            maze.set_walls(x, y, known);
+        }
+    }
+
+    floodfill::flood(&mut maze);
+    maze
+}
+
+/// Returns the neighbours of `pos` that are part of the spanning tree, and their count.
+fn tree_neighbours<const W: usize, const H: usize>(
+    rules: &Rules<W, H>,
+    pos: Vecu,
+) -> ([(Vecu, Relative); 4], usize) {
+    let mut neighbours = [(Vecu::new(), Relative::North); 4];
+    let mut count = 0;
+
+    for dir in Relative::iter() {
+        if let Some(other) = Walls::<W, H>::neighbour(pos, *dir) {
+            if rules.is_tree(other) {
+                neighbours[count] = (other, *dir);
+                count += 1;
+            }
+        }
+    }
+
+    (neighbours, count)
+}
+
+/// Carves the spanning tree using randomized depth-first search.
+fn backtracker<const W: usize, const H: usize>(
+    rules: &Rules<W, H>,
+    walls: &mut Walls<W, H>,
+    random: &mut Random,
+) {
+    let mut visited = [[false; W]; H];
+    let mut stack = [[Vecu::new(); W]; H];
+    let stack = stack.as_flattened_mut();
+    let mut len = 0;
+
+    let root = Vecu { x: 0, y: 1 };
+    visited[root.y as usize][root.x as usize] = true;
+    stack[len] = root;
+    len += 1;
+
+    while len > 0 {
+        let pos = stack[len - 1];
+        let (neighbours, count) = tree_neighbours(rules, pos);
+
+        let mut unvisited = [(Vecu::new(), Relative::North); 4];
+        let mut options = 0;
+        for (other, dir) in neighbours.iter().take(count) {
+            if !visited[other.y as usize][other.x as usize] {
+                unvisited[options] = (*other, *dir);
+                options += 1;
+            }
+        }
+
+        if options == 0 {
+            len -= 1;
+            continue;
+        }
+
+        let (other, dir) = unvisited[random.below(options)];
+        walls.set(pos, dir, false);
+        visited[other.y as usize][other.x as usize] = true;
+        stack[len] = other;
+        len += 1;
+    }
+}
+
+/// Carves the spanning tree using randomized Prim's algorithm.
+fn prim<const W: usize, const H: usize>(
+    rules: &Rules<W, H>,
+    walls: &mut Walls<W, H>,
+    random: &mut Random,
+) {
+    let mut in_tree = [[false; W]; H];
+    let mut in_frontier = [[false; W]; H];
+    let mut frontier = [[Vecu::new(); W]; H];
+    let frontier = frontier.as_flattened_mut();
+    let mut len = 0;
+
+    let root = Vecu { x: 0, y: 1 };
+    in_tree[root.y as usize][root.x as usize] = true;
+    let mut added = root;
+
+    loop {
+        let (neighbours, count) = tree_neighbours(rules, added);
+        for (other, _) in neighbours.iter().take(count) {
+            let (x, y) = (other.x as usize, other.y as usize);
+            if !in_tree[y][x] && !in_frontier[y][x] {
+                in_frontier[y][x] = true;
+                frontier[len] = *other;
+                len += 1;
+            }
+        }
+
+        if len == 0 {
+            break;
+        }
+
+        // take a random segment out of the frontier and connect it to a random tree neighbour
+        let i = random.below(len);
+        let pos = frontier[i];
+        frontier[i] = frontier[len - 1];
+        len -= 1;
+
+        let (neighbours, count) = tree_neighbours(rules, pos);
+        let mut connected = [(Vecu::new(), Relative::North); 4];
+        let mut options = 0;
+        for (other, dir) in neighbours.iter().take(count) {
+            if in_tree[other.y as usize][other.x as usize] {
+                connected[options] = (*other, *dir);
+                options += 1;
+            }
+        }
+
+        let (_, dir) = connected[random.below(options)];
+        walls.set(pos, dir, false);
+        in_tree[pos.y as usize][pos.x as usize] = true;
+        added = pos;
+    }
+}
+
+/// Returns the root of the set containing `i`, compressing the path on the way.
+fn find(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+/// Carves the spanning tree using randomized Kruskal's algorithm.
+fn kruskal<const W: usize, const H: usize>(
+    rules: &Rules<W, H>,
+    walls: &mut Walls<W, H>,
+    random: &mut Random,
+) {
+    let mut edges = [[[(Vecu::new(), Relative::East); 2]; W]; H];
+    let edges = edges.as_flattened_mut().as_flattened_mut();
+    let mut len = 0;
+
+    for x in 0..W as u8 {
+        for y in 0..H as u8 {
+            let pos = Vecu { x, y };
+            for dir in [Relative::East, Relative::South] {
+                match Walls::<W, H>::neighbour(pos, dir) {
+                    Some(other) if rules.is_tree(pos) && rules.is_tree(other) => {
+                        edges[len] = (pos, dir);
+                        len += 1;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    // Fisher-Yates shuffle
+    for i in (1..len).rev() {
+        edges.swap(i, random.below(i + 1));
+    }
+
+    let mut parents = [[0; W]; H];
+    let parents = parents.as_flattened_mut();
+    for (i, parent) in parents.iter_mut().enumerate() {
+        *parent = i;
+    }
+
+    for (pos, dir) in edges.iter().take(len) {
+        let other = Walls::<W, H>::neighbour(*pos, *dir).unwrap();
+        let a = find(parents, pos.x as usize + pos.y as usize * W);
+        let b = find(parents, other.x as usize + other.y as usize * W);
+
+        if a != b {
+            parents[a] = b;
+            walls.set(*pos, *dir, false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::maze::generate::{generate, Algorithm};
+    use crate::maze::{Assumption, Maze, Relative};
+    use crate::vec::Vecu;
+
+    const ALGORITHMS: [Algorithm; 3] =
+        [Algorithm::Backtracker, Algorithm::Prim, Algorithm::Kruskal];
+
+    /// Returns the number of open walls between two segments.
+    fn open_walls<const W: usize, const H: usize>(maze: &Maze<W, H>) -> usize {
+        let mut count = 0;
+        for x in 0..W as u8 {
+            for y in 0..H as u8 {
+                let segment = maze.segment(x, y);
+                for (i, dir) in Relative::iter().enumerate() {
+                    if !segment.walls[i].is_present() && segment.relative(maze, dir).is_some() {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count / 2
+    }
+
+    /// Asserts that `maze` follows the competition rules.
+    fn assert_rules<const W: usize, const H: usize>(maze: &Maze<W, H>) {
+        let (cx, cy) = ((W / 2) as u8, (H / 2) as u8);
+
+        // every segment is known and can be reached from the start
+        for x in 0..W as u8 {
+            for y in 0..H as u8 {
+                assert!(maze.is_known(x, y));
+                assert!(maze
+                    .shortest_route(Vecu { x, y }, Assumption::Pessimistic)
+                    .is_some());
+            }
+        }
+
+        let start = maze.segment(0, 0);
+        assert_eq!(1, start.walls.iter().filter(|w| !w.is_present()).count());
+        assert!(!start.walls[Relative::South as usize].is_present());
+
+        // the goal is open inside and has one entrance
+        let mut entrances = 0;
+        for x in cx - 1..=cx {
+            for y in cy - 1..=cy {
+                let segment = maze.segment(x, y);
+                for (i, dir) in Relative::iter().enumerate() {
+                    let other = segment.relative(maze, dir).unwrap().pos();
+                    let inside =
+                        (cx - 1..=cx).contains(&other.x) && (cy - 1..=cy).contains(&other.y);
+                    if inside {
+                        assert!(!segment.walls[i].is_present());
+                    } else if !segment.walls[i].is_present() {
+                        entrances += 1;
+                    }
+                }
+            }
+        }
+        assert_eq!(1, entrances);
+
+        // every post touches a wall, except for the one in the middle of the goal
+        for x in 1..W as u8 {
+            for y in 1..H as u8 {
+                if x == cx && y == cy {
+                    continue;
+                }
+
+                let corner = maze.segment(x - 1, y - 1);
+                let opposite = maze.segment(x, y);
+                assert!(
+                    corner.walls[Relative::East as usize].is_present()
+                        || corner.walls[Relative::South as usize].is_present()
+                        || opposite.walls[Relative::North as usize].is_present()
+                        || opposite.walls[Relative::West as usize].is_present(),
+                    "post at {:?} has no walls",
+                    Vecu { x, y }
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn perfect() {
+        for algorithm in ALGORITHMS {
+            for seed in 0..20 {
+                let maze: Maze<16, 16> = generate(algorithm, seed, 0.);
+                assert_rules(&maze);
+                // a spanning tree, plus the cycle inside the goal
+                assert_eq!(16 * 16 - 1 + 1, open_walls(&maze));
+
+                let maze: Maze<6, 8> = generate(algorithm, seed, 0.);
+                assert_rules(&maze);
+                assert_eq!(6 * 8 - 1 + 1, open_walls(&maze));
+            }
+        }
+    }
+
+    #[test]
+    fn loops() {
+        for algorithm in ALGORITHMS {
+            let maze: Maze<16, 16> = generate(algorithm, 7, 0.2);
+            assert_rules(&maze);
+            assert!(open_walls(&maze) > 16 * 16);
+
+            let maze: Maze<32, 32> = generate(algorithm, 7, 1.);
+            assert_rules(&maze);
+        }
+    }
+
+    #[test]
+    fn deterministic() {
+        for algorithm in ALGORITHMS {
+            let one: Maze<16, 16> = generate(algorithm, 42, 0.1);
+            let two: Maze<16, 16> = generate(algorithm, 42, 0.1);
+            let other: Maze<16, 16> = generate(algorithm, 43, 0.1);
+
+            assert_eq!(one.to_maz(), two.to_maz());
+            assert_ne!(one.to_maz(), other.to_maz());
+        }
+    }
+}
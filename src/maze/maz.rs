@@ -0,0 +1,206 @@
+use crate::floodfill;
+use crate::maze::{Maze, Relative, Wall};
+use crate::vec::Vecu;
+
+/// An error that occurred while reading a `.maz` file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MazError {
+    /// The file does not contain exactly one byte per segment.
+    InvalidLength { expected: usize, found: usize },
+    /// The wall on side `relative` of the segment at `pos` is not present in the neighbouring
+    /// segment, or the other way around.
+    Inconsistent { pos: Vecu, relative: Relative },
+}
+
+impl core::fmt::Display for MazError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MazError::InvalidLength { expected, found } => {
+                write!(f, "expected {} bytes, found {}", expected, found)
+            }
+            MazError::Inconsistent { pos, relative } => write!(
+                f,
+                "the {:?} wall of {:?} does not match its neighbour",
+                relative, pos
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MazError {}
+
+/// Returns the bit of the wall on side `relative` in a `.maz` segment byte.
+///
+/// Bits follow the order of [Relative], with north as the least significant bit.
+/// The format counts `y` upwards from the start, so its north is [Relative::South] in this crate.
+fn bit(relative: Relative) -> u8 {
+    match relative {
+        Relative::North | Relative::South => 1 << relative.opposite() as u8,
+        Relative::East | Relative::West => 1 << relative as u8,
+    }
+}
+
+/// Returns the index of the byte of the segment at `x, y` in a `.maz` file.
+/// Segments are stored column by column, starting at the origin.
+fn index<const H: usize>(x: u8, y: u8) -> usize {
+    x as usize * H + y as usize
+}
+
+impl<const W: usize, const H: usize> Maze<W, H> {
+    /// Reads a maze from the binary `.maz` format, which has one byte per segment holding a
+    /// bitmask of its walls. Classic mazes are 256 bytes, half-size mazes 1024 bytes.
+    ///
+    /// Every wall in the file is known. Walls shared by two segments must be present in both.
+    pub fn from_maz(bytes: &[u8]) -> Result<Self, MazError> {
+        if bytes.len() != W * H {
+            return Err(MazError::InvalidLength {
+                expected: W * H,
+                found: bytes.len(),
+            });
+        }
+
+        let mut maze = Maze::new();
+        for x in 0..W as u8 {
+            for y in 0..H as u8 {
+                let byte = bytes[index::<H>(x, y)];
+                let segment = maze.segment(x, y);
+
+                let mut walls = [Wall::Unknown; 4];
+                for (i, dir) in Relative::iter().enumerate() {
+                    let present = byte & bit(*dir) != 0;
+
+                    if let Some(relative) = segment.relative(&maze, dir) {
+                        let pos = relative.pos();
+                        let other = bytes[index::<H>(pos.x, pos.y)] & bit(dir.opposite()) != 0;
+                        if present != other {
+                            return Err(MazError::Inconsistent {
+                                pos: segment.pos(),
+                                relative: *dir,
+                            });
+                        }
+                    }
+
+                    walls[i] = present.into();
+                }
+
+                maze.set_walls(x, y, walls);
+            }
+        }
+
+        floodfill::flood(&mut maze);
+        Ok(maze)
+    }
+
+    /// Writes this maze in the binary `.maz` format, as read by [Maze::from_maz].
+    /// Walls that are not known to be present are written as open.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if `bytes` does not have exactly one byte per segment.
+    pub fn write_maz(&self, bytes: &mut [u8]) {
+        assert_eq!(W * H, bytes.len(), "Expected one byte per segment");
+
+        for x in 0..W as u8 {
+            for y in 0..H as u8 {
+                let segment = self.segment(x, y);
+
+                bytes[index::<H>(x, y)] = Relative::iter()
+                    .enumerate()
+                    .filter(|(i, _)| segment.walls[*i].is_present())
+                    .fold(0, |byte, (_, dir)| byte | bit(*dir));
+            }
+        }
+    }
+
+    /// Returns this maze in the binary `.maz` format, as read by [Maze::from_maz].
+    /// Walls that are not known to be present are written as open.
+    #[cfg(any(feature = "std", test))]
+    pub fn to_maz(&self) -> Vec<u8> {
+        let mut bytes = vec![0; W * H];
+        self.write_maz(&mut bytes);
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::maze::maz::MazError;
+    use crate::maze::Wall::{Open, Present};
+    use crate::maze::{Maze, Relative};
+    use crate::vec::Vecu;
+
+    #[test]
+    fn read() {
+        // +---+---+
+        // |       |
+        // +   +---+
+        // |   |   |
+        // +---+---+
+        let bytes = [0b1110, 0b1001, 0b1111, 0b0111];
+        let maze: Maze<2, 2> = Maze::from_maz(&bytes).unwrap();
+
+        assert_eq!([Present, Present, Open, Present], maze.segment(0, 0).walls);
+        assert_eq!([Open, Open, Present, Present], maze.segment(0, 1).walls);
+        assert_eq!(
+            [Present, Present, Present, Present],
+            maze.segment(1, 0).walls
+        );
+        assert_eq!([Present, Present, Present, Open], maze.segment(1, 1).walls);
+        assert_eq!(bytes.to_vec(), maze.to_maz());
+    }
+
+    #[test]
+    fn matches_ascii() {
+        let ascii = "\
++---+---+---+
+|           |
++   +---+   +
+|   |   |   |
++   +   +   +
+|   |       |
++---+---+---+
+";
+        let maze: Maze<3, 3> = Maze::from_ascii(ascii).unwrap();
+        let maz: Maze<3, 3> = Maze::from_maz(&maze.to_maz()).unwrap();
+
+        assert_eq!(ascii, maz.to_ascii());
+    }
+
+    #[test]
+    fn half_size() {
+        let mut maze: Maze<32, 32> = Maze::new();
+        maze.update_walls(0, 0, [true, true, false, true]);
+        maze.update_walls(31, 31, [false, true, true, false]);
+        maze.update_walls(15, 16, [true, false, true, false]);
+
+        let bytes = maze.to_maz();
+        assert_eq!(1024, bytes.len());
+        assert_eq!(0b1110, bytes[0]);
+        assert_eq!(0b0011, bytes[1023]);
+
+        let read: Maze<32, 32> = Maze::from_maz(&bytes).unwrap();
+        assert_eq!(bytes, read.to_maz());
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            MazError::InvalidLength {
+                expected: 256,
+                found: 4
+            },
+            crate::maze::ClassicMaze::from_maz(&[0; 4]).err().unwrap()
+        );
+
+        // the east wall of the origin is missing in its neighbour
+        let bytes = [0b1110, 0b1001, 0b0111, 0b0111];
+        assert_eq!(
+            MazError::Inconsistent {
+                pos: Vecu { x: 0, y: 0 },
+                relative: Relative::East
+            },
+            Maze::<2, 2>::from_maz(&bytes).err().unwrap()
+        );
+    }
+}
@@ -1,53 +1,175 @@
+use crate::error::Error;
 use crate::vec::Vecu;
-use crate::{MAZE_HEIGHT_U8, MAZE_SIZE, MAZE_WIDTH_U8};
+use crate::{MAZE_HEIGHT_USIZE, MAZE_WIDTH_USIZE};
+
+pub mod diagonal;
+pub mod profile;
 
 pub const ACCELERATION_MS2: f64 = 2.;
 pub const MAX_SPEED_MS: f64 = 5.;
+pub const TURN_SPEED_MS: f64 = 1.;
+
+/// The amount of segments a path can hold, as a multiple of the amount of segments in the maze.
+/// Paths taken during an exploration can visit segments more than once.
+pub const PATH_CAPACITY_FACTOR: usize = 4;
+
+/// A list of at most [PATH_CAPACITY_FACTOR] times `W * H` values, such as one value for every
+/// segment of a [Path] through a maze of `W` by `H` segments.
+///
+/// ### Description
+///
+/// The values are stored inline, such that a buffer never allocates. It dereferences to a slice
+/// of the values that have been pushed.
+#[derive(Clone)]
+pub struct Buffer<T, const W: usize, const H: usize> {
+    /// The values, of which the first `len` are used
+    values: [[[T; W]; H]; PATH_CAPACITY_FACTOR],
+    len: usize,
+}
+
+impl<T: Copy, const W: usize, const H: usize> Buffer<T, W, H> {
+    /// Returns a new empty buffer, of which the unused values are `fill`.
+    pub fn new(fill: T) -> Self {
+        Buffer {
+            values: [[[fill; W]; H]; PATH_CAPACITY_FACTOR],
+            len: 0,
+        }
+    }
+
+    /// Returns the maximum amount of values this buffer can hold.
+    pub fn capacity(&self) -> usize {
+        W * H * PATH_CAPACITY_FACTOR
+    }
+
+    /// Returns the values that have been pushed.
+    pub fn as_slice(&self) -> &[T] {
+        &self.values.as_flattened().as_flattened()[..self.len]
+    }
+
+    /// Returns the values that have been pushed, which may be changed.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        let len = self.len;
+        &mut self.values.as_flattened_mut().as_flattened_mut()[..len]
+    }
+
+    /// Appends `value` to the end of this buffer.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if this buffer already holds [Buffer::capacity] values.
+    pub fn push(&mut self, value: T) {
+        self.try_push(value).expect("Buffer is full");
+    }
+
+    /// Appends `value` to the end of this buffer like [Buffer::push], or returns
+    /// [Error::PathFull] if this buffer is full.
+    pub fn try_push(&mut self, value: T) -> Result<(), Error> {
+        if self.len >= self.capacity() {
+            return Err(Error::PathFull);
+        }
+
+        let len = self.len;
+        self.values.as_flattened_mut().as_flattened_mut()[len] = value;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Keeps the first `len` values, and removes the rest.
+    pub fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len);
+    }
+
+    /// Removes every value from this buffer.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl<T: Copy, const W: usize, const H: usize> core::ops::Deref for Buffer<T, W, H> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
 
-/// Represents a path that may be taken
-pub struct Path {
+impl<T: Copy, const W: usize, const H: usize> core::ops::DerefMut for Buffer<T, W, H> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T: Copy + PartialEq, const W: usize, const H: usize> PartialEq for Buffer<T, W, H> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: Copy + core::fmt::Debug, const W: usize, const H: usize> core::fmt::Debug
+    for Buffer<T, W, H>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+/// Represents a path that may be taken through a maze of `W` by `H` segments.
+/// A path holds at most [PATH_CAPACITY_FACTOR] times `W * H` segments.
+pub struct Path<const W: usize, const H: usize> {
     /// The taken segments
-    segments: Vec<Vecu>,
+    segments: Buffer<Vecu, W, H>,
     optimized: bool,
 }
 
-impl Path {
+/// A path through the classic 16x16 competition maze.
+pub type ClassicPath = Path<MAZE_WIDTH_USIZE, MAZE_HEIGHT_USIZE>;
+
+impl<const W: usize, const H: usize> Path<W, H> {
     /// Returns a new path instance
     pub fn new() -> Self {
         Path {
-            segments: Vec::with_capacity(MAZE_SIZE),
+            segments: Buffer::new(Vecu::new()),
             optimized: false,
         }
     }
 
+    /// Returns the maximum amount of segments this path can hold.
+    pub fn capacity(&self) -> usize {
+        self.segments.capacity()
+    }
+
+    /// Returns the taken segments.
+    pub fn as_slice(&self) -> &[Vecu] {
+        self.segments.as_slice()
+    }
+
     /// Whether the current value at a path is a turn or not.
     fn is_turn(&self, current: usize) -> bool {
-        let prev = self.segments[current - 1];
-        let next = self.segments[current + 1];
+        let prev = self.as_slice()[current - 1];
+        let next = self.as_slice()[current + 1];
 
         prev.x != next.x && prev.y != next.y
     }
 
-    /// Returns the current estimated amount of time to complete this path.
-    /// Returns undefined results on unoptimized paths.
+    /// Returns the current estimated amount of time in seconds to complete this path,
+    /// following the [profile::Profile] with the default [profile::Limits].
+    ///
+    /// ### Panics
+    ///
+    /// Panics if this path has not been optimized.
     pub fn time_to_complete(&self) -> f64 {
-        assert!(
-            self.optimized,
-            "cannot calculate time to complete on an unoptimized path"
-        );
-
-        let mut time = 0.;
+        self.try_time_to_complete()
+            .expect("cannot calculate time to complete on an unoptimized path")
+    }
 
-        for (i, _x) in self.segments.iter().enumerate() {
-            if i > 0 && i < self.segments.len() - 1 {
-                if self.is_turn(i) {
-                    time += 0.5;
-                }
-                time += 1.;
-            }
+    /// Returns the current estimated amount of time in seconds to complete this path like
+    /// [Path::time_to_complete], or [Error::UnoptimizedPath] if this path has not been optimized.
+    pub fn try_time_to_complete(&self) -> Result<f64, Error> {
+        if !self.optimized {
+            return Err(Error::UnoptimizedPath);
         }
 
-        time
+        Ok(self.drive_time(&profile::Limits::default()))
     }
 
     /// Returns the current size of this path.
@@ -62,18 +184,12 @@ impl Path {
     /// Return the _n_-th segment that this path has taken.
     /// If the segment has not been visited yet, returns [Segment::new].
     pub fn segment(&self, index: usize) -> Option<Vecu> {
-        if index >= self.len() {
-            return None;
-        }
-        Some(self.segments[index])
+        self.as_slice().get(index).copied()
     }
 
     /// Returns the current head of the path.
     pub fn head(&self) -> Option<Vecu> {
-        if self.is_empty() {
-            return None;
-        }
-        Some(self.segments[self.len() - 1])
+        self.as_slice().last().copied()
     }
 
     /// Appends a segment to the path.
@@ -81,8 +197,19 @@ impl Path {
     /// ### Arguments
     ///
     /// - `segment` - The `Segment` to append to the path.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the path already holds [Path::capacity] segments.
+    /// Use [Path::try_append] to handle this instead.
     pub fn append(&mut self, segment: Vecu) {
-        self.segments.push(segment);
+        self.try_append(segment).expect("Path is full");
+    }
+
+    /// Appends a segment to the path like [Path::append], or returns [Error::PathFull] if the
+    /// path is full.
+    pub fn try_append(&mut self, segment: Vecu) -> Result<(), Error> {
+        self.segments.try_push(segment)
     }
 
     /// Appends segments to the path.
@@ -90,9 +217,13 @@ impl Path {
     /// ### Arguments
     ///
     /// - `segments` - The `Segment`s to append to the path.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the path cannot hold all segments within [Path::capacity].
     pub fn append_all(&mut self, segments: &[Vecu]) {
         for segment in segments.iter() {
-            self.segments.push(*segment);
+            self.append(*segment);
         }
     }
 
@@ -102,68 +233,84 @@ impl Path {
     ///
     /// - `vec` - The vec to check for containment.
     pub fn contains(&self, vec: Vecu) -> bool {
-        for i in (0..self.len()).rev() {
-            if self.segments[i] == vec {
-                return true;
-            }
-        }
-        false
+        self.as_slice().iter().rev().any(|segment| *segment == vec)
+    }
+
+    /// Removes every segment from this path, keeping its storage.
+    pub fn clear(&mut self) {
+        self.segments.clear();
+        self.optimized = false;
+    }
+
+    /// Reverses the order of the segments of this path.
+    pub fn reverse(&mut self) {
+        self.segments.reverse();
     }
 
     /// Optimizes this path by removing any cycle that has the same start and end point.
-    /// Assigns `self.segments` to a new optimized [Vec].
+    /// The segments are optimized in place.
     /// todo! avoid bulk optimization and optimize as soon as append_all/append is called
     pub fn optimize(&mut self) {
-        let mut optimized: Vec<Vecu> = Vec::with_capacity(MAZE_SIZE);
+        let len = self.len();
+        let segments = self.segments.as_mut_slice();
+        // the optimized segments are never ahead of the segment being read
+        let mut optimized = 0;
 
         let mut i = 0;
-        'outer: while i < self.segments.len() {
-            let pos = self.segments[i];
-
-            for j in i + 1..self.segments.len() {
-                if self.segments[j] != pos {
-                    continue;
-                }
+        while i < len {
+            let pos = segments[i];
 
-                i += j - i;
-                continue 'outer;
+            // skip the cycle until the next time this segment is visited
+            if let Some(j) = segments[i + 1..].iter().position(|other| *other == pos) {
+                i += j + 1;
+                continue;
             }
 
-            optimized.push(pos);
+            segments[optimized] = pos;
+            optimized += 1;
             i += 1;
         }
 
-        self.segments = optimized;
+        self.segments.truncate(optimized);
         self.optimized = true;
     }
 
+    /// Returns the amount of times this path changes direction.
+    /// Turning around counts as a single change.
+    pub fn turns(&self) -> usize {
+        (1..self.len().saturating_sub(1))
+            .filter(|i| self.is_turn(*i) || self.as_slice()[i - 1] == self.as_slice()[i + 1])
+            .count()
+    }
+
     /// Returns whether this path has been optimized.
     pub fn optimized(&self) -> bool {
         self.optimized
     }
 }
 
-impl Default for Path {
+impl<const W: usize, const H: usize> Default for Path<W, H> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl core::fmt::Debug for Path {
+impl<const W: usize, const H: usize> core::fmt::Debug for Path<W, H> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        writeln!(
+        write!(f, "Path | Length {:?}", self.len())?;
+        write!(
             f,
-            "Path | Length {:?} | Time to complete {:?} sec",
-            self.segments.len(),
+            " | Time to complete {:?} sec",
             if self.optimized {
                 self.time_to_complete()
             } else {
                 0.
             }
         )?;
+        writeln!(f)?;
 
-        for y in 0..MAZE_HEIGHT_U8 {
-            for x in 0..MAZE_WIDTH_U8 {
+        for y in 0..H as u8 {
+            for x in 0..W as u8 {
                 if self.contains(Vecu { x, y }) {
                     write!(f, "#")?;
                 } else {
@@ -180,12 +327,12 @@ impl core::fmt::Debug for Path {
 #[cfg(test)]
 mod tests {
     use crate::maze::Segment;
-    use crate::path::Path;
+    use crate::path::ClassicPath;
     use crate::vec::Vecu;
 
     #[test]
     fn path() {
-        let mut path = Path::new();
+        let mut path = ClassicPath::new();
 
         path.append(Vecu::new());
         assert_eq!(1, path.len());
@@ -195,9 +342,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn count_turns() {
+        let mut path = ClassicPath::new();
+        assert_eq!(0, path.turns());
+
+        for (x, y) in [(0, 0), (0, 1), (0, 2), (1, 2), (1, 3), (1, 2), (2, 2)] {
+            path.append(Vecu { x, y });
+        }
+        assert_eq!(4, path.turns());
+    }
+
     #[test]
     fn optimize() {
-        let mut path = Path::new();
+        let mut path = ClassicPath::new();
 
         path.append(Vecu { x: 0, y: 0 });
         path.append(Vecu { x: 1, y: 0 });
@@ -217,7 +375,7 @@ mod tests {
 
     #[test]
     fn optimize_avoid_remove_root() {
-        let mut path = Path::new();
+        let mut path = ClassicPath::new();
 
         path.append(Vecu { x: 0, y: 0 });
         path.append(Vecu { x: 1, y: 0 });
@@ -238,7 +396,7 @@ mod tests {
 
     #[test]
     fn turns() {
-        let mut one = Path::new();
+        let mut one = ClassicPath::new();
 
         one.append(Vecu { x: 0, y: 0 });
         one.append(Vecu { x: 1, y: 0 });
@@ -248,7 +406,7 @@ mod tests {
         one.append(Vecu { x: 3, y: 2 });
         one.append(Vecu { x: 3, y: 3 });
 
-        let mut two = Path::new();
+        let mut two = ClassicPath::new();
 
         two.append(Vecu { x: 0, y: 0 });
         two.append(Vecu { x: 1, y: 0 });
@@ -268,7 +426,7 @@ mod tests {
 
     #[test]
     fn turns_equal_len() {
-        let mut one = Path::new();
+        let mut one = ClassicPath::new();
 
         one.append(Vecu { x: 0, y: 0 });
         one.append(Vecu { x: 1, y: 0 });
@@ -281,7 +439,7 @@ mod tests {
         one.append(Vecu { x: 1, y: 3 });
         one.append(Vecu { x: 0, y: 3 });
 
-        let mut two = Path::new();
+        let mut two = ClassicPath::new();
 
         two.append(Vecu { x: 0, y: 0 });
         two.append(Vecu { x: 0, y: 1 });
@@ -0,0 +1,454 @@
+use crate::path::{Buffer, Path};
+use crate::vec::Vecu;
+
+/// The side to which the mouse turns.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// A movement of the mouse during a speed run that may cut diagonally across segments.
+///
+/// Every movement starts and ends at the center of a segment side, except for the first and
+/// last [Movement::Straight], which start and end at the center of a segment.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Movement {
+    /// Drives straight along a row or column for the amount of half segments.
+    Straight(u16),
+    /// Drives diagonally through the amount of segments, cutting the corner of each one.
+    Diagonal(u16),
+    /// Turns 90 degrees within a single segment.
+    Turn90(Side),
+    /// Turns around a post within two segments.
+    Turn180(Side),
+    /// Turns 45 degrees from a straight onto a diagonal within a single segment.
+    In45(Side),
+    /// Turns 135 degrees from a straight onto a diagonal within two segments.
+    In135(Side),
+    /// Turns 45 degrees from a diagonal onto a straight within a single segment.
+    Out45(Side),
+    /// Turns 135 degrees from a diagonal onto a straight within two segments.
+    Out135(Side),
+    /// Turns 90 degrees from a diagonal onto another diagonal around a post within two segments.
+    V90(Side),
+}
+
+impl Movement {
+    /// Returns the angle in degrees that the mouse turns during this movement.
+    /// Turns to the right are positive.
+    pub fn angle(&self) -> i16 {
+        let (angle, side) = match *self {
+            Movement::Straight(_) | Movement::Diagonal(_) => return 0,
+            Movement::Turn90(side) | Movement::V90(side) => (90, side),
+            Movement::Turn180(side) => (180, side),
+            Movement::In45(side) | Movement::Out45(side) => (45, side),
+            Movement::In135(side) | Movement::Out135(side) => (135, side),
+        };
+
+        match side {
+            Side::Left => -angle,
+            Side::Right => angle,
+        }
+    }
+}
+
+/// The cost of every kind of [Movement], such as the time in milliseconds it takes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Costs {
+    /// The cost of every half segment of a straight.
+    pub half: u32,
+    /// The cost of every segment of a diagonal.
+    pub diagonal: u32,
+    pub turn_90: u32,
+    pub turn_180: u32,
+    pub in_45: u32,
+    pub in_135: u32,
+    pub out_45: u32,
+    pub out_135: u32,
+    pub v_90: u32,
+}
+
+impl Default for Costs {
+    fn default() -> Self {
+        Costs {
+            half: 50,
+            diagonal: 71,
+            turn_90: 150,
+            turn_180: 300,
+            in_45: 100,
+            in_135: 200,
+            out_45: 100,
+            out_135: 200,
+            v_90: 150,
+        }
+    }
+}
+
+impl Costs {
+    /// Returns the total cost of `movements`.
+    pub fn cost(&self, movements: &[Movement]) -> u32 {
+        movements
+            .iter()
+            .map(|movement| match *movement {
+                Movement::Straight(n) => self.half * n as u32,
+                Movement::Diagonal(n) => self.diagonal * n as u32,
+                Movement::Turn90(_) => self.turn_90,
+                Movement::Turn180(_) => self.turn_180,
+                Movement::In45(_) => self.in_45,
+                Movement::In135(_) => self.in_135,
+                Movement::Out45(_) => self.out_45,
+                Movement::Out135(_) => self.out_135,
+                Movement::V90(_) => self.v_90,
+            })
+            .sum()
+    }
+}
+
+/// Returns the side to which the mouse turns in the segment `current`,
+/// or `None` if it drives straight through.
+fn turn(previous: Vecu, current: Vecu, next: Vecu) -> Option<Side> {
+    let (ix, iy) = (
+        current.x as i16 - previous.x as i16,
+        current.y as i16 - previous.y as i16,
+    );
+    let (ox, oy) = (
+        next.x as i16 - current.x as i16,
+        next.y as i16 - current.y as i16,
+    );
+
+    // north is towards a lower y, so clockwise is a positive cross product
+    match ix * oy - iy * ox {
+        0 => None,
+        cross if cross > 0 => Some(Side::Right),
+        _ => Some(Side::Left),
+    }
+}
+
+/// The movements of a [Path] through a maze of `W` by `H` segments. A path never has more
+/// movements than segments.
+pub type Movements<const W: usize, const H: usize> = Buffer<Movement, W, H>;
+
+/// Adds `movement` to `movements`, merging consecutive straights and diagonals.
+fn push<const W: usize, const H: usize>(movements: &mut Movements<W, H>, movement: Movement) {
+    match (movements.last_mut(), movement) {
+        (_, Movement::Straight(0) | Movement::Diagonal(0)) => {}
+        (Some(Movement::Straight(n)), Movement::Straight(m)) => *n += m,
+        (Some(Movement::Diagonal(n)), Movement::Diagonal(m)) => *n += m,
+        _ => movements.push(movement),
+    }
+}
+
+/// Converts the turns of a diagonal run into movements.
+///
+/// ### Description
+///
+/// The run enters the diagonal with [Movement::In45], or [Movement::In135] when the first two
+/// turns are to the same side. It leaves the diagonal in the same way with [Movement::Out45] or
+/// [Movement::Out135]. In between, every turn to the other side than the previous one keeps the
+/// mouse on the diagonal, and two turns to the same side turn it onto the next diagonal with
+/// [Movement::V90].
+fn diagonal_run<const W: usize, const H: usize>(movements: &mut Movements<W, H>, turns: &[Side]) {
+    let k = turns.len();
+    let mut i = if turns[0] == turns[1] {
+        push(movements, Movement::In135(turns[0]));
+        2
+    } else {
+        push(movements, Movement::In45(turns[0]));
+        1
+    };
+
+    let exit = if k - 2 >= i && turns[k - 2] == turns[k - 1] {
+        k - 2
+    } else {
+        k - 1
+    };
+
+    while i < exit {
+        if i + 1 < exit && turns[i] == turns[i + 1] {
+            push(movements, Movement::V90(turns[i]));
+            i += 2;
+        } else {
+            push(movements, Movement::Diagonal(1));
+            i += 1;
+        }
+    }
+
+    if exit == k - 2 {
+        push(movements, Movement::Out135(turns[k - 1]));
+    } else {
+        push(movements, Movement::Out45(turns[k - 1]));
+    }
+}
+
+impl<const W: usize, const H: usize> Path<W, H> {
+    /// Converts this path into movements along rows and columns only.
+    pub fn to_orthogonal(&self) -> Movements<W, H> {
+        self.to_movements(false)
+    }
+
+    /// Converts this path into movements that cut diagonally across staircases.
+    ///
+    /// ### Description
+    ///
+    /// The path is split into runs of consecutive segments where the mouse turns, separated by
+    /// segments where it drives straight through. A single turn becomes a [Movement::Turn90], and
+    /// two turns to the same side become a [Movement::Turn180]. Every other run becomes a
+    /// diagonal, unless it contains three consecutive turns to the same side, which circle a
+    /// single post and are kept as [Movement::Turn90]s.
+    ///
+    /// ### Implementation
+    ///
+    /// The turns of every run are converted by a small state machine: entering the diagonal,
+    /// following it, and leaving it, as described by [diagonal_run]. The first and last
+    /// half segment of the path are straights, and every segment that is driven straight
+    /// through adds two half segments.
+    pub fn to_diagonal(&self) -> Movements<W, H> {
+        self.to_movements(true)
+    }
+
+    /// Returns the cheaper of [Path::to_orthogonal] and [Path::to_diagonal] according to `costs`.
+    pub fn to_fastest_movements(&self, costs: &Costs) -> Movements<W, H> {
+        let orthogonal = self.to_orthogonal();
+        let diagonal = self.to_diagonal();
+
+        if costs.cost(&diagonal) < costs.cost(&orthogonal) {
+            diagonal
+        } else {
+            orthogonal
+        }
+    }
+
+    /// Converts this path into movements, with diagonals if `diagonals` is true.
+    fn to_movements(&self, diagonals: bool) -> Movements<W, H> {
+        let mut movements = Buffer::new(Movement::Straight(0));
+        if self.len() < 2 {
+            return movements;
+        }
+
+        push(&mut movements, Movement::Straight(1));
+
+        let segments = self.as_slice();
+        let mut turns: Buffer<Side, W, H> = Buffer::new(Side::Left);
+        for i in 1..self.len() {
+            let side = if i + 1 < self.len() {
+                turn(segments[i - 1], segments[i], segments[i + 1])
+            } else {
+                None
+            };
+
+            if let Some(side) = side {
+                turns.push(side);
+                continue;
+            }
+
+            Self::turn_run(&mut movements, &turns, diagonals);
+            turns.clear();
+            push(
+                &mut movements,
+                Movement::Straight(if i + 1 < self.len() { 2 } else { 1 }),
+            );
+        }
+
+        movements
+    }
+
+    /// Converts a run of consecutive turns into movements.
+    fn turn_run(movements: &mut Movements<W, H>, turns: &[Side], diagonals: bool) {
+        let circles = turns
+            .windows(3)
+            .any(|window| window[0] == window[1] && window[1] == window[2]);
+
+        match turns {
+            [] => {}
+            [side] => push(movements, Movement::Turn90(*side)),
+            [first, second] if first == second => push(movements, Movement::Turn180(*first)),
+            _ if diagonals && !circles => diagonal_run(movements, turns),
+            _ => {
+                for side in turns {
+                    push(movements, Movement::Turn90(*side));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::path::diagonal::Movement::{
+        Diagonal, In135, In45, Out135, Out45, Straight, Turn180, Turn90, V90,
+    };
+    use crate::path::diagonal::Side::{Left, Right};
+    use crate::path::diagonal::{Costs, Movement};
+    use crate::path::ClassicPath;
+    use crate::vec::Vecu;
+
+    /// Returns a path through `segments`.
+    fn path(segments: &[(u8, u8)]) -> ClassicPath {
+        let mut path = ClassicPath::new();
+        for (x, y) in segments {
+            path.append(Vecu { x: *x, y: *y });
+        }
+        path
+    }
+
+    /// Returns the total angle of `movements`.
+    fn angle(movements: &[Movement]) -> i16 {
+        movements.iter().map(Movement::angle).sum()
+    }
+
+    #[test]
+    fn staircase() {
+        let staircase = path(&[(0, 0), (0, 1), (1, 1), (1, 2), (2, 2), (2, 3), (3, 3)]);
+
+        assert_eq!(
+            vec![
+                Straight(1),
+                Turn90(Left),
+                Turn90(Right),
+                Turn90(Left),
+                Turn90(Right),
+                Turn90(Left),
+                Straight(1)
+            ],
+            staircase.to_orthogonal().as_slice()
+        );
+        assert_eq!(
+            vec![
+                Straight(1),
+                In45(Left),
+                Diagonal(3),
+                Out45(Left),
+                Straight(1)
+            ],
+            staircase.to_diagonal().as_slice()
+        );
+
+        let costs = Costs::default();
+        assert!(costs.cost(&staircase.to_diagonal()) < costs.cost(&staircase.to_orthogonal()));
+        assert_eq!(
+            staircase.to_diagonal(),
+            staircase.to_fastest_movements(&costs)
+        );
+    }
+
+    #[test]
+    fn straight_and_turns() {
+        assert_eq!(
+            Vec::<Movement>::new(),
+            path(&[(0, 0)]).to_diagonal().as_slice()
+        );
+        assert_eq!(
+            vec![Straight(6)],
+            path(&[(0, 0), (0, 1), (0, 2), (0, 3)])
+                .to_diagonal()
+                .as_slice()
+        );
+
+        // a single turn and a turn around a post are never diagonal
+        assert_eq!(
+            vec![Straight(1), Turn90(Left), Straight(1)],
+            path(&[(0, 0), (0, 1), (1, 1)]).to_diagonal().as_slice()
+        );
+        assert_eq!(
+            vec![Straight(1), Turn180(Left), Straight(1)],
+            path(&[(0, 0), (0, 1), (1, 1), (1, 0)])
+                .to_diagonal()
+                .as_slice()
+        );
+    }
+
+    #[test]
+    fn entries_and_exits() {
+        // left, then right: two separate turns
+        assert_eq!(
+            vec![
+                Straight(1),
+                Turn90(Left),
+                Straight(2),
+                Turn90(Right),
+                Straight(1)
+            ],
+            path(&[(0, 0), (0, 1), (1, 1), (2, 1), (2, 2)])
+                .to_diagonal()
+                .as_slice()
+        );
+
+        // right, right, left, left: in 135, out 135
+        assert_eq!(
+            vec![Straight(1), In135(Right), Out135(Left), Straight(1)],
+            path(&[(2, 0), (2, 1), (1, 1), (1, 0), (0, 0), (0, 1)])
+                .to_diagonal()
+                .as_slice()
+        );
+
+        // left, right, right: in 45, out 135
+        let segments = [
+            (0, 0),
+            (0, 1),
+            (1, 1),
+            (2, 1),
+            (2, 0),
+            (3, 0),
+            (3, 1),
+            (3, 2),
+        ];
+        assert_eq!(
+            vec![
+                Straight(1),
+                Turn90(Left),
+                Straight(2),
+                In45(Left),
+                Out135(Right),
+                Straight(3)
+            ],
+            path(&segments).to_diagonal().as_slice()
+        );
+    }
+
+    #[test]
+    fn v90() {
+        // left, right, right, left: in 45, v 90 to the right, out 45
+        let zigzag = path(&[(0, 0), (0, 1), (1, 1), (1, 2), (0, 2), (0, 3)]);
+        let movements = zigzag.to_diagonal();
+
+        assert_eq!(
+            vec![
+                Straight(1),
+                In45(Left),
+                V90(Right),
+                Out45(Left),
+                Straight(1)
+            ],
+            movements.as_slice()
+        );
+        assert_eq!(0, angle(&movements));
+    }
+
+    #[test]
+    fn angles() {
+        // every conversion turns the mouse as far as the path itself
+        let spiral = path(&[
+            (0, 0),
+            (0, 1),
+            (1, 1),
+            (1, 2),
+            (2, 2),
+            (2, 1),
+            (3, 1),
+            (3, 2),
+            (3, 3),
+            (2, 3),
+            (2, 4),
+            (1, 4),
+            (1, 5),
+        ]);
+
+        let orthogonal = spiral.to_orthogonal();
+        let diagonal = spiral.to_diagonal();
+        assert_eq!(angle(&orthogonal), angle(&diagonal));
+        assert!(diagonal
+            .iter()
+            .any(|movement| matches!(movement, Diagonal(_))));
+    }
+}
@@ -0,0 +1,196 @@
+use crate::path::{Buffer, Path, ACCELERATION_MS2, MAX_SPEED_MS, TURN_SPEED_MS};
+use crate::MAZE_BLOCK_M;
+
+/// The limits of the motion of the mouse.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Limits {
+    /// The acceleration and deceleration in m/s².
+    pub acceleration: f64,
+    /// The highest speed in m/s.
+    pub max_speed: f64,
+    /// The highest speed in m/s when passing through a segment where the path turns.
+    pub turn_speed: f64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            acceleration: ACCELERATION_MS2,
+            max_speed: MAX_SPEED_MS,
+            turn_speed: TURN_SPEED_MS,
+        }
+    }
+}
+
+/// The planned motion of the mouse along a [Path] through a maze of `W` by `H` segments.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Profile<const W: usize, const H: usize> {
+    /// The speed in m/s at the center of every segment of the path.
+    pub speeds: Buffer<f64, W, H>,
+    /// The time in seconds at which the center of every segment of the path is reached.
+    pub times: Buffer<f64, W, H>,
+}
+
+impl<const W: usize, const H: usize> Profile<W, H> {
+    /// Returns the total time in seconds to drive the path.
+    pub fn time(&self) -> f64 {
+        self.times.last().copied().unwrap_or(0.)
+    }
+}
+
+/// Returns the time to drive `distance` when starting at speed `from` and ending at speed `to`,
+/// accelerating as much as `limits` allow.
+fn move_time(distance: f64, from: f64, to: f64, limits: &Limits) -> f64 {
+    let a = limits.acceleration;
+    let peak = libm::sqrt((2. * a * distance + from * from + to * to) / 2.).min(limits.max_speed);
+
+    let accelerate = (peak * peak - from * from) / (2. * a);
+    let decelerate = (peak * peak - to * to) / (2. * a);
+    let cruise = (distance - accelerate - decelerate).max(0.);
+
+    (peak - from) / a + cruise / peak + (peak - to) / a
+}
+
+impl<const W: usize, const H: usize> Path<W, H> {
+    /// Returns the planned motion of the mouse along this path.
+    ///
+    /// ### Description
+    ///
+    /// The path is split into straight runs between the segments where it turns. The mouse
+    /// starts and stops at rest, passes every turn at most at the turn speed, and accelerates,
+    /// cruises and decelerates on every run, as allowed by `limits`.
+    ///
+    /// ### Implementation
+    ///
+    /// Every segment gets a speed limit, which is zero for the first and last segment, the turn
+    /// speed for turns and the max speed otherwise. In a forward pass, the speed of every segment
+    /// is lowered to what can be reached by accelerating from the previous segment over
+    /// [MAZE_BLOCK_M]. In a backward pass, it is lowered to what still allows decelerating to
+    /// the next segment. Then, every move between two segments follows a trapezoidal profile
+    /// between both speeds.
+    ///
+    /// ### Arguments
+    ///
+    /// - `limits` - The limits of the motion of the mouse.
+    pub fn profile(&self, limits: &Limits) -> Profile<W, H> {
+        let speeds = self.speeds(limits);
+        let mut times = Buffer::new(0.);
+        let mut time = 0.;
+        for i in 0..speeds.len() {
+            if i > 0 {
+                time += move_time(MAZE_BLOCK_M, speeds[i - 1], speeds[i], limits);
+            }
+            times.push(time);
+        }
+
+        Profile { speeds, times }
+    }
+
+    /// Returns the total time in seconds to drive this path, like [Profile::time] of
+    /// [Path::profile] without storing the time at every segment.
+    pub(crate) fn drive_time(&self, limits: &Limits) -> f64 {
+        let speeds = self.speeds(limits);
+        speeds
+            .windows(2)
+            .map(|pair| move_time(MAZE_BLOCK_M, pair[0], pair[1], limits))
+            .sum()
+    }
+
+    /// Returns the speed at every segment of this path, as described in [Path::profile].
+    fn speeds(&self, limits: &Limits) -> Buffer<f64, W, H> {
+        let len = self.len();
+        let mut speeds = Buffer::new(0.);
+
+        for i in 0..len {
+            speeds.push(if i == 0 || i == len - 1 {
+                0.
+            } else if self.is_turn(i) {
+                limits.turn_speed.min(limits.max_speed)
+            } else if self.as_slice()[i - 1] == self.as_slice()[i + 1] {
+                // turning around
+                0.
+            } else {
+                limits.max_speed
+            });
+        }
+
+        let reachable =
+            |speed: f64| libm::sqrt(speed * speed + 2. * limits.acceleration * MAZE_BLOCK_M);
+        for i in 1..len {
+            speeds[i] = speeds[i].min(reachable(speeds[i - 1]));
+        }
+        for i in (0..len.saturating_sub(1)).rev() {
+            speeds[i] = speeds[i].min(reachable(speeds[i + 1]));
+        }
+
+        speeds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::path::profile::{move_time, Limits};
+    use crate::path::ClassicPath;
+    use crate::vec::Vecu;
+    use crate::MAZE_BLOCK_M;
+
+    #[test]
+    fn straight() {
+        let limits = Limits::default();
+        let mut path = ClassicPath::new();
+        for y in 0..16 {
+            path.append(Vecu { x: 0, y });
+        }
+
+        let profile = path.profile(&limits);
+        assert_eq!(16, profile.speeds.len());
+        assert_eq!(0., profile.speeds[0]);
+        assert_eq!(0., profile.speeds[15]);
+
+        // symmetric, and fastest in the middle
+        for i in 0..16 {
+            assert!((profile.speeds[i] - profile.speeds[15 - i]).abs() < 1e-9);
+            assert!(profile.speeds[i] <= limits.max_speed);
+        }
+        assert!(profile.speeds[7] > profile.speeds[1]);
+
+        // accelerating to the middle and braking from it without cruising
+        let half = 7.5 * MAZE_BLOCK_M;
+        let expected = 2. * (2. * half / limits.acceleration).sqrt();
+        assert!((profile.time() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn turn_speed() {
+        let limits = Limits {
+            acceleration: 10.,
+            max_speed: 3.,
+            turn_speed: 0.5,
+        };
+        let mut path = ClassicPath::new();
+        for x in 0..8 {
+            path.append(Vecu { x, y: 0 });
+        }
+        for y in 1..8 {
+            path.append(Vecu { x: 7, y });
+        }
+
+        let profile = path.profile(&limits);
+        assert_eq!(0.5, profile.speeds[7]);
+        assert_eq!(3., profile.speeds[3]);
+        assert!(profile.times.windows(2).all(|times| times[0] < times[1]));
+    }
+
+    #[test]
+    fn cruise() {
+        let limits = Limits {
+            acceleration: 1.,
+            max_speed: 1.,
+            turn_speed: 1.,
+        };
+
+        // 0.5 m to accelerate and brake, and 1 m of cruising
+        assert!((move_time(2., 0., 0., &limits) - 3.).abs() < 1e-9);
+        assert!((move_time(1.5, 0., 1., &limits) - 2.).abs() < 1e-9);
+    }
+}
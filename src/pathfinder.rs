@@ -1,21 +1,50 @@
-use crate::map::Map;
+use crate::error::Error;
 use crate::maze::{Maze, Relative, Segment};
 use crate::path::Path;
 use crate::vec::Vecu;
-use crate::MAZE_SIZE;
-use std::collections::VecDeque;
+
+/// The buffers used by the pathfinder, which can be reused between calls so that finding the
+/// next segment never allocates.
+///
+/// ### Description
+///
+/// All buffers have a fixed size that depends only on `W` and `H`. Keeping a single instance
+/// around, for example in static memory on the robot, also avoids placing them on the stack
+/// during every call.
+pub struct Scratch<const W: usize, const H: usize> {
+    /// The route found by the last call to [next_with] that got stuck.
+    route: Path<W, H>,
+}
+
+impl<const W: usize, const H: usize> Scratch<W, H> {
+    /// Returns new empty buffers.
+    pub fn new() -> Self {
+        Scratch { route: Path::new() }
+    }
+
+    /// Returns the route found by the last call to [next_with] that returned `None`.
+    pub fn route(&self) -> &Path<W, H> {
+        &self.route
+    }
+}
+
+impl<const W: usize, const H: usize> Default for Scratch<W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 /// The result of an attempted pathfinding using [next].
-pub enum Result {
+pub enum Result<'a, const W: usize, const H: usize> {
     /// Indicates that a segment has been found that is not a neighbour of the head of the path.
-    /// Contains the new segment and the path from the head to the segment.
-    Stuck(Vec<Vecu>),
+    /// Contains the path from the head to the segment, stored in the buffer passed to [next].
+    Stuck(&'a Path<W, H>),
 
     /// Indicates that a valid neighbour has been found as the next segment.
     Found(Segment),
 }
 
-impl Result {
+impl<'a, const W: usize, const H: usize> Result<'a, W, H> {
     /// Whether this result is a dead end or not.
     pub fn is_dead_end(&self) -> bool {
         matches!(*self, Result::Stuck(_))
@@ -35,7 +64,7 @@ impl Result {
     }
 
     /// Unwraps the stuck value.
-    pub fn unwrap_stuck(&self) -> &Vec<Vecu> {
+    pub fn unwrap_stuck(&self) -> &'a Path<W, H> {
         match self {
             Result::Stuck(s) => s,
             _ => panic!("Called `Result::unwrap_stuck` on a non-Stuck value"),
@@ -43,48 +72,140 @@ impl Result {
     }
 }
 
-/// Represents the target mode of the pathfinding.
-pub enum Target {
-    Center,
-    Origin,
-}
-
 /// Attempts to find the next segment based on `maze` and the taken `path`.
 ///
 /// ### Description
 ///
-/// Returns the segment `n` with the strictly smallest distance to the target
-/// that is reachable from and a neighbour of `p`, with `p` being the last element in `path`.
+/// Returns the segment `n` with the smallest distance to the goal that is reachable from
+/// and a neighbour of `p`, with `p` being the last element in `path`. The distance of `n` must be
+/// strictly smaller than the distance of `p`.
+///
+/// The distances are expected to be maintained by [crate::floodfill::flood], in which case
+/// such a neighbour always exists when the goal is reachable from `p`.
 ///
 /// ### Implementation
 ///
-/// Let `n_i` be any neighbour of `p` that is reachable and has not yet been visited.
+/// Let `n_i` be any neighbour of `p` that is reachable.
 /// Any `n_i` is considered as a potential minimal segment.
 /// If `n_j` with `i != j` has a lower distance than the current minimal segment, `j` becomes the
-/// minimal segment.
+/// minimal segment. When distances are equal, a segment that has not been visited yet is preferred.
 ///
-/// If the minimal segment is disconnected from the head, returns a path to the minimal segment.
-/// This is not guaranteed to be the shortest path.
+/// If there is no neighbour with a smaller distance, returns a path to the nearest segment that
+/// has not been visited yet. This is not guaranteed to be the shortest path.
 ///
 /// ### Arguments
 ///
 /// - `maze` - The current maze.
 /// - `path` - The taken path.
-/// - `target` - The target type.
+/// - `route` - The buffer to store the route in when no neighbour is found. It never holds more
+///   segments than `path`, so it cannot overflow.
 ///
 /// ### Returns
 ///
 /// - [Result::Found] - A valid next segment has been found.
-/// - [Result::Stuck] - A valid next segment has been found, but it is not directly attached
-///   to the head of `path`. Returns the path to the valid next segment.
+/// - [Result::Stuck] - No neighbour with a smaller distance exists.
+///   Returns the path to the nearest unvisited segment, stored in `route`.
 ///   This path excludes the head of `path`.
-pub fn next(maze: &Maze, path: &Path) -> Result {
-    // the smallest segment so far
+///
+/// ### Panics
+///
+/// Panics if `path` is empty, or if its head is outside `maze`.
+/// Use [try_next] to handle these instead.
+pub fn next<'a, const W: usize, const H: usize>(
+    maze: &Maze<W, H>,
+    path: &Path<W, H>,
+    route: &'a mut Path<W, H>,
+) -> Result<'a, W, H> {
+    try_next(maze, path, route).unwrap_or_else(|e| panic!("Failed to find the next segment: {}", e))
+}
+
+/// Attempts to find the next segment based on `maze` and the taken `path` like [next].
+///
+/// ### Returns
+///
+/// - [Error::EmptyPath] - `path` has no segments.
+/// - [Error::OutOfBounds] - The head of `path` is outside `maze`, or another segment of `path`
+///   that is read while searching for the nearest unvisited segment.
+pub fn try_next<'a, const W: usize, const H: usize>(
+    maze: &Maze<W, H>,
+    path: &Path<W, H>,
+    route: &'a mut Path<W, H>,
+) -> core::result::Result<Result<'a, W, H>, Error> {
+    check_head(maze, path)?;
+
+    Ok(match smallest_neighbour(maze, path) {
+        Some(segment) => Result::Found(segment),
+        None => {
+            try_nearest_unvisited(maze, path, route)?;
+            Result::Stuck(route)
+        }
+    })
+}
+
+/// Attempts to find the next segment like [next], using the buffers in `scratch`.
+///
+/// ### Arguments
+///
+/// - `maze` - The current maze.
+/// - `path` - The taken path.
+/// - `scratch` - The buffers to store the route in when no neighbour is found.
+///
+/// ### Returns
+///
+/// - `Some` - A valid next segment has been found.
+/// - `None` - No neighbour with a smaller distance exists. The path to the nearest unvisited
+///   segment is stored in [Scratch::route], like [Result::Stuck].
+///
+/// ### Panics
+///
+/// Panics like [next].
+pub fn next_with<const W: usize, const H: usize>(
+    maze: &Maze<W, H>,
+    path: &Path<W, H>,
+    scratch: &mut Scratch<W, H>,
+) -> Option<Segment> {
+    try_next_with(maze, path, scratch)
+        .unwrap_or_else(|e| panic!("Failed to find the next segment: {}", e))
+}
+
+/// Attempts to find the next segment like [next_with], returning the same errors as [try_next].
+pub fn try_next_with<const W: usize, const H: usize>(
+    maze: &Maze<W, H>,
+    path: &Path<W, H>,
+    scratch: &mut Scratch<W, H>,
+) -> core::result::Result<Option<Segment>, Error> {
+    check_head(maze, path)?;
+
+    let segment = smallest_neighbour(maze, path);
+    if segment.is_none() {
+        try_nearest_unvisited(maze, path, &mut scratch.route)?;
+    }
+    Ok(segment)
+}
+
+/// Returns the head of `path`, or an error if `path` is empty or its head is outside `maze`.
+fn check_head<const W: usize, const H: usize>(
+    maze: &Maze<W, H>,
+    path: &Path<W, H>,
+) -> core::result::Result<Vecu, Error> {
+    let head = path.head().ok_or(Error::EmptyPath)?;
+    maze.try_segment(head.x, head.y)?;
+    Ok(head)
+}
+
+/// Returns the reachable neighbour of the head of `path` with the smallest distance that is
+/// strictly smaller than the distance of the head, as described in [next].
+fn smallest_neighbour<const W: usize, const H: usize>(
+    maze: &Maze<W, H>,
+    path: &Path<W, H>,
+) -> Option<Segment> {
     let head = path.head().expect("Failed to find path head");
     let current = maze.segment_vec(head);
+    // the smallest segment so far, and whether it has been visited
+    let mut min: Option<(Segment, bool)> = None;
 
     'dirs: for (j, dir) in Relative::iter().enumerate() {
-        if current.walls[j] {
+        if current.walls[j].is_present() {
             continue 'dirs;
         }
 
@@ -94,123 +215,73 @@ pub fn next(maze: &Maze, path: &Path) -> Result {
         }
 
         let segment = segment.unwrap();
-        if path.contains(segment.pos()) {
+        if segment.distance >= current.distance {
             continue 'dirs;
         }
 
-        if segment.distance < current.distance {
-            return Result::Found(segment);
+        let visited = path.contains(segment.pos());
+        let smaller = match min {
+            None => true,
+            Some((other, other_visited)) => {
+                segment.distance < other.distance
+                    || (segment.distance == other.distance && other_visited && !visited)
+            }
+        };
+
+        if smaller {
+            min = Some((segment, visited));
         }
     }
 
-    Result::Stuck(nearest_unvisited(maze, path))
+    min.map(|(segment, _)| segment)
 }
 
-/// Updates the distances in `maze` when a dead end is reached.
+/// Stores the path from the head of `path` to the nearest segment that has not been visited in
+/// `to`. Any segments in `to` are removed first.
 ///
 /// ### Description
 ///
-/// Updates the distances in a dead end by BFS such that, for any node `n` in the dead end,
-/// `d(n, goal) == d(start, path.segment(path.len() - 2)) + d(path.segment(path.len() - 2), n)`.
-/// Here, `d` is guaranteed to be the shortest distance between two nodes.
-///
-/// ### Implementation
-///
-/// Every element in the path that is between the last two occurrences of the `root` node
-/// `path.segment(path.len() - 2)` is added to the possible options. If no two
-/// occurrences are found, returns and does nothing.
-///
-/// Then, the last occurrence of `root` is added to `to_explore`, which details which
-/// nodes are to be explored, and `root` is added to `explored`, which details which nodes
-/// to not explore again.
-///
-/// In a loop, BFS is performed. For every node in the options, its distance is updated to
-/// the parent node's distance, plus 1. Nodes that have been explored are added to `explored`.
-/// When there are no more nodes to explore, exits.
+/// Walks `path` back from its head until a segment is found that has an open neighbour that is
+/// not in `path`. The stored path retraces `path` up to that segment, and ends in the
+/// neighbour. The head of `path` is excluded. If every reachable segment has been visited,
+/// stores `path` in reverse, excluding its head. Either way, `to` never holds more segments
+/// than `path`.
 ///
 /// ### Arguments
 ///
 /// - `maze` - The current maze.
 /// - `path` - The taken path.
-pub fn update_distances(maze: &mut Maze, path: &Path) {
-    assert!(
-        !path.optimized(),
-        "Only unoptimized paths can have distances updated"
-    );
-
-    let head_idx = path.len() - 2;
-    let root = path.segment(head_idx).unwrap();
-    let mut previous_head_idx = usize::MAX; // the first time head re-appeared
-    for i in (0..path.len() - 2).rev() {
-        if path.segment(i).unwrap() == root {
-            previous_head_idx = i;
-            break;
-        }
-    }
-
-    // updating distances cannot be applied if there is no loop
-    if previous_head_idx == head_idx || previous_head_idx == usize::MAX {
-        return;
-    }
-
-    // contains all vecs that can be explored.
-    let mut options: Map<bool> = Map::new();
-    for i in previous_head_idx..head_idx {
-        options.insert(path.segment(i).unwrap(), true);
-    }
-
-    let mut to_explore: VecDeque<Vecu> = VecDeque::new();
-    // contains the vecs that have been explored, with the value being the parent vec.
-    // for the root, value is `None`.
-    let mut explored: Map<Option<Vecu>> = Map::new();
-
-    {
-        explored.insert(root, None);
-        to_explore.push_back(root);
-    }
-
-    while !to_explore.is_empty() {
-        let current_pos = to_explore
-            .pop_front()
-            .expect("Failed to find unvisited node in entire maze");
-        let current_segment = maze.segment_vec(current_pos);
-
-        // check all directions for unvisited segments
-        'dirs: for (i, dir) in Relative::iter().enumerate() {
-            if current_segment.walls[i] {
-                continue 'dirs;
-            }
-
-            let relative = current_segment.relative(maze, dir);
-            if relative.is_none() {
-                // wall or OOB
-                continue 'dirs;
-            }
-
-            let segment = relative.unwrap();
-            let new_pos = segment.pos();
-            if explored.contains_key(&new_pos) || !options.contains_key(&new_pos) {
-                // already explored or not in the options
-                continue 'dirs;
-            }
-
-            explored.insert(new_pos, Some(current_pos));
-            to_explore.push_back(new_pos);
-
-            maze.update_distance(new_pos.x, new_pos.y, current_segment.distance + 1);
-        }
-    }
+/// - `to` - The path to store the route in.
+///
+/// ### Panics
+///
+/// Panics if a segment of `path` that is walked back is outside `maze`.
+/// Use [try_nearest_unvisited] to handle this instead.
+pub fn nearest_unvisited<const W: usize, const H: usize>(
+    maze: &Maze<W, H>,
+    path: &Path<W, H>,
+    to: &mut Path<W, H>,
+) {
+    try_nearest_unvisited(maze, path, to)
+        .unwrap_or_else(|e| panic!("Failed to find the nearest unvisited segment: {}", e))
 }
 
-/// TODO! document
-pub fn nearest_unvisited(maze: &Maze, path: &Path) -> Vec<Vecu> {
-    let mut to: Vec<Vecu> = Vec::with_capacity(MAZE_SIZE);
+/// Stores the path to the nearest segment that has not been visited in `to` like
+/// [nearest_unvisited], or returns [Error::OutOfBounds] if a segment of `path` that is walked
+/// back is outside `maze`.
+pub fn try_nearest_unvisited<const W: usize, const H: usize>(
+    maze: &Maze<W, H>,
+    path: &Path<W, H>,
+    to: &mut Path<W, H>,
+) -> core::result::Result<(), Error> {
+    to.clear();
 
     for i in (0..path.len()).rev() {
-        let current = maze.segment_vec(path.segment(i).expect("Failed to find path segment"));
+        let pos = path.segment(i).expect("Failed to find path segment");
+        let current = maze.try_segment(pos.x, pos.y)?;
 
         'dirs: for (j, dir) in Relative::iter().enumerate() {
-            if current.walls[j] {
+            if current.walls[j].is_present() {
                 continue 'dirs;
             }
 
@@ -224,25 +295,27 @@ pub fn nearest_unvisited(maze: &Maze, path: &Path) -> Vec<Vecu> {
             }
 
             if i != path.len() - 1 {
-                to.push(current.pos());
+                to.append(current.pos());
             }
-            to.push(segment.pos());
-            return to;
+            to.append(segment.pos());
+            return Ok(());
         }
 
         if i != path.len() - 1 {
-            to.push(current.pos());
+            to.append(current.pos());
         }
     }
 
     // if there are no other paths, use old path
-    to
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::maze::Maze;
-    use crate::path::Path;
+    use crate::error::Error;
+    use crate::goal::Goal;
+    use crate::maze::{ClassicMaze, Maze};
+    use crate::path::{ClassicPath, Path};
     use crate::pathfinder;
     use crate::vec::Vecu;
 
@@ -253,11 +326,12 @@ mod tests {
     ///
     /// - `maze` - The maze.
     /// - `path` - The path that has been taken so far. Is updated by this method.
-    fn find_negative(maze: &mut Maze, path: &mut Path) {
+    fn find_negative<const W: usize, const H: usize>(maze: &mut Maze<W, H>, path: &mut Path<W, H>) {
+        let mut route = Path::new();
         path.append(Vecu::new());
 
         loop {
-            let result = pathfinder::next(&maze, &path);
+            let result = pathfinder::next(maze, path, &mut route);
 
             match result {
                 pathfinder::Result::Found(next) => {
@@ -268,8 +342,7 @@ mod tests {
                     }
                 }
                 pathfinder::Result::Stuck(next) => {
-                    path.append_all(&next);
-                    pathfinder::update_distances(maze, &path);
+                    path.append_all(next.as_slice());
                 }
             }
         }
@@ -277,8 +350,8 @@ mod tests {
 
     #[test]
     fn next() {
-        let mut maze = Maze::new();
-        let mut path = Path::new();
+        let mut maze = ClassicMaze::new();
+        let mut path = ClassicPath::new();
 
         find_negative(&mut maze, &mut path);
 
@@ -302,10 +375,32 @@ mod tests {
     }
 
     #[test]
-    fn next_equal_distances() {
-        let mut maze = Maze::new();
+    fn next_small_maze() {
+        let mut maze: Maze<5, 5> = Maze::new();
+        let mut path = Path::new();
+
+        find_negative(&mut maze, &mut path);
+
+        assert_eq!(5, path.len());
+        assert_eq!(Vecu { x: 2, y: 2 }, path.head().unwrap());
+    }
+
+    #[test]
+    fn next_goal() {
+        let mut maze: Maze<5, 5> = Maze::with_goal(Goal::cell(Vecu { x: 4, y: 4 }));
         let mut path = Path::new();
 
+        find_negative(&mut maze, &mut path);
+
+        assert_eq!(9, path.len());
+        assert_eq!(Vecu { x: 4, y: 4 }, path.head().unwrap());
+    }
+
+    #[test]
+    fn next_equal_distances() {
+        let mut maze = ClassicMaze::new();
+        let mut path = ClassicPath::new();
+
         maze.update_distance(0, 0, 15);
         maze.update_distance(1, 0, 15);
         maze.update_distance(2, 0, 15);
@@ -320,8 +415,8 @@ mod tests {
 
     #[test]
     fn next_guided() {
-        let mut maze = Maze::new();
-        let mut path = Path::new();
+        let mut maze = ClassicMaze::new();
+        let mut path = ClassicPath::new();
 
         // ###
         // # #
@@ -340,8 +435,8 @@ mod tests {
 
     #[test]
     fn next_guided_diagonal() {
-        let mut maze = Maze::new();
-        let mut path = Path::new();
+        let mut maze = ClassicMaze::new();
+        let mut path = ClassicPath::new();
 
         // ######
         // #  ###
@@ -366,8 +461,8 @@ mod tests {
 
     #[test]
     fn general_deadend() {
-        let mut maze = Maze::new();
-        let mut path = Path::new();
+        let mut maze = ClassicMaze::new();
+        let mut path = ClassicPath::new();
 
         // #####
         // #   #
@@ -379,11 +474,11 @@ mod tests {
 
         find_negative(&mut maze, &mut path);
 
+        // the dead end is known to be longer, so it is never entered
         assert_eq!(Vecu { x: 0, y: 0 }, path.segment(0).unwrap());
         assert_eq!(Vecu { x: 1, y: 0 }, path.segment(1).unwrap());
-        assert_eq!(Vecu { x: 2, y: 0 }, path.segment(2).unwrap());
-        assert_eq!(Vecu { x: 1, y: 0 }, path.segment(3).unwrap());
-        assert_eq!(Vecu { x: 1, y: 1 }, path.segment(4).unwrap());
+        assert_eq!(Vecu { x: 1, y: 1 }, path.segment(2).unwrap());
+        assert!(!path.contains(Vecu { x: 2, y: 0 }));
 
         assert_eq!(14, maze.segment(0, 0).distance);
         assert_eq!(13, maze.segment(1, 0).distance);
@@ -393,8 +488,8 @@ mod tests {
 
     #[test]
     fn general_nontrivial_deadend() {
-        let mut maze = Maze::new();
-        let mut path = Path::new();
+        let mut maze = ClassicMaze::new();
+        let mut path = ClassicPath::new();
 
         // #######
         // #     #
@@ -413,20 +508,11 @@ mod tests {
 
         find_negative(&mut maze, &mut path);
 
+        // the dead end is known to be longer, so it is never entered
         assert_eq!(Vecu { x: 0, y: 0 }, path.segment(0).unwrap());
-        assert_eq!(Vecu { x: 1, y: 0 }, path.segment(1).unwrap());
-        assert_eq!(Vecu { x: 2, y: 0 }, path.segment(2).unwrap());
-        assert_eq!(Vecu { x: 3, y: 0 }, path.segment(3).unwrap());
-        assert_eq!(Vecu { x: 3, y: 1 }, path.segment(4).unwrap());
-        assert_eq!(Vecu { x: 2, y: 1 }, path.segment(5).unwrap());
-        assert_eq!(Vecu { x: 1, y: 1 }, path.segment(6).unwrap());
-        assert_eq!(Vecu { x: 2, y: 1 }, path.segment(7).unwrap());
-        assert_eq!(Vecu { x: 3, y: 1 }, path.segment(8).unwrap());
-        assert_eq!(Vecu { x: 3, y: 0 }, path.segment(9).unwrap());
-        assert_eq!(Vecu { x: 2, y: 0 }, path.segment(10).unwrap());
-        assert_eq!(Vecu { x: 1, y: 0 }, path.segment(11).unwrap());
-        assert_eq!(Vecu { x: 0, y: 0 }, path.segment(12).unwrap());
-        assert_eq!(Vecu { x: 0, y: 1 }, path.segment(13).unwrap());
+        assert_eq!(Vecu { x: 0, y: 1 }, path.segment(1).unwrap());
+        assert_eq!(Vecu { x: 0, y: 2 }, path.segment(2).unwrap());
+        assert!(!path.contains(Vecu { x: 1, y: 0 }));
 
         assert_eq!(14, maze.segment(0, 0).distance);
         assert_eq!(15, maze.segment(1, 0).distance);
@@ -437,4 +523,28 @@ mod tests {
         assert_eq!(17, maze.segment(2, 1).distance);
         assert_eq!(18, maze.segment(3, 1).distance);
     }
+
+    #[test]
+    fn try_next() {
+        let maze: Maze<5, 5> = Maze::new();
+        let mut path = Path::new();
+        let mut route = Path::new();
+
+        assert!(matches!(
+            pathfinder::try_next(&maze, &path, &mut route),
+            Err(Error::EmptyPath)
+        ));
+
+        path.append(Vecu { x: 0, y: 7 });
+        assert!(matches!(
+            pathfinder::try_next(&maze, &path, &mut route),
+            Err(Error::OutOfBounds { .. })
+        ));
+
+        let mut path = Path::new();
+        path.append(Vecu::new());
+        assert!(pathfinder::try_next(&maze, &path, &mut route)
+            .unwrap()
+            .is_found());
+    }
 }
@@ -0,0 +1,476 @@
+use crate::maze::{Maze, Relative};
+use crate::vec::{Vecf, Vecu};
+use crate::MAZE_BLOCK_M;
+
+/// The gravitational acceleration in m/s².
+pub const GRAVITY_MS2: f64 = 9.81;
+
+/// The thickness of a wall of the maze in m.
+pub const WALL_THICKNESS_M: f64 = 0.012;
+
+/// The highest amount of contacts reported by a single step.
+pub const MAX_CONTACTS: usize = 4;
+
+/// The physical properties of a two-wheeled, differential-drive mouse.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Robot {
+    /// The mass in kg.
+    pub mass: f64,
+    /// The moment of inertia around the vertical axis through the center in kg·m².
+    pub inertia: f64,
+    /// The radius of the body in m, which is treated as a circle when colliding with walls.
+    pub radius: f64,
+    /// The radius of a wheel in m.
+    pub wheel_radius: f64,
+    /// The distance between the wheels in m.
+    pub track: f64,
+    /// The highest torque of a motor in N·m, which is reached when the wheel stands still.
+    pub max_torque: f64,
+    /// The angular speed of a wheel in rad/s at which its motor cannot deliver any torque.
+    pub no_load_speed: f64,
+    /// The coefficient of friction between the wheels and the floor, which limits the force a
+    /// wheel can apply before it slips.
+    pub friction: f64,
+    /// The coefficient of rolling resistance of the wheels.
+    pub rolling_resistance: f64,
+}
+
+impl Default for Robot {
+    fn default() -> Self {
+        Robot {
+            mass: 0.1,
+            inertia: 0.1 * 0.04 * 0.04 / 2.,
+            radius: 0.04,
+            wheel_radius: 0.012,
+            track: 0.07,
+            max_torque: 0.005,
+            no_load_speed: 500.,
+            friction: 0.8,
+            rolling_resistance: 0.01,
+        }
+    }
+}
+
+/// The values of the left and right wheel.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Wheels {
+    pub left: f64,
+    pub right: f64,
+}
+
+/// The position in m and the heading in radians of the mouse.
+///
+/// The position is measured from the outer corner of the origin segment, with `x` growing
+/// towards [Relative::East] and `y` towards [Relative::South], like the segments of a maze.
+/// A heading of zero faces east, and a positive heading rotates from east towards south.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Pose {
+    pub position: Vecf,
+    pub heading: f64,
+}
+
+impl Pose {
+    /// Returns the pose in the center of the origin segment, facing [Relative::South].
+    pub fn start() -> Self {
+        Pose {
+            position: Vecf {
+                x: MAZE_BLOCK_M / 2.,
+                y: MAZE_BLOCK_M / 2.,
+            },
+            heading: core::f64::consts::FRAC_PI_2,
+        }
+    }
+}
+
+/// A collision of the mouse with a wall during a step.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Contact {
+    /// The segment the wall belongs to.
+    pub segment: Vecu,
+    /// The side of `segment` the wall is on.
+    pub relative: Relative,
+    /// The speed in m/s at which the mouse moved into the wall.
+    pub speed: f64,
+}
+
+/// Returns the ends of the center line of the wall on side `relative` of the segment at `x, y`,
+/// with the smallest coordinates first.
+pub(crate) fn wall_ends(x: u8, y: u8, relative: Relative) -> (Vecf, Vecf) {
+    let (x0, y0) = (x as f64 * MAZE_BLOCK_M, y as f64 * MAZE_BLOCK_M);
+    let (x1, y1) = (x0 + MAZE_BLOCK_M, y0 + MAZE_BLOCK_M);
+    let (from, to) = match relative {
+        Relative::North => ((x0, y0), (x1, y0)),
+        Relative::East => ((x1, y0), (x1, y1)),
+        Relative::South => ((x0, y1), (x1, y1)),
+        Relative::West => ((x0, y0), (x0, y1)),
+    };
+
+    (
+        Vecf {
+            x: from.0,
+            y: from.1,
+        },
+        Vecf { x: to.0, y: to.1 },
+    )
+}
+
+/// A simulation of the physics of a [Robot] driving through a maze of `W` by `H` segments,
+/// to test controllers against without the robot.
+///
+/// ### Description
+///
+/// Every step, the torque of both motors is applied for a fixed amount of time. The torque is
+/// limited by the motor, which delivers less torque the faster the wheel spins, and the force
+/// of a wheel is limited by the friction with the floor. The mouse is treated as a circle that
+/// cannot pass through walls that are present in the maze, or through the border of the maze.
+///
+/// ### Implementation
+///
+/// The wheels roll without slipping sideways, so the motion is fully described by the forward
+/// and angular velocity of the mouse. These are integrated using semi-implicit Euler
+/// integration. After moving, the mouse is pushed out of any wall it overlaps with, and the part
+/// of its velocity that moves into the wall is removed.
+pub struct Physics<const W: usize, const H: usize> {
+    maze: Maze<W, H>,
+    robot: Robot,
+    dt: f64,
+    time: f64,
+    pose: Pose,
+    /// The forward velocity in m/s.
+    velocity: f64,
+    /// The angular velocity in rad/s, positive when turning from east towards south.
+    angular_velocity: f64,
+    contacts: heapless::Vec<Contact, MAX_CONTACTS>,
+}
+
+impl<const W: usize, const H: usize> Physics<W, H> {
+    /// Creates a new simulation of `robot` standing still at [Pose::start] in `maze`.
+    ///
+    /// ### Arguments
+    ///
+    /// - `maze` - The maze, of which walls that are present are collided with.
+    /// - `robot` - The physical properties of the mouse.
+    /// - `dt` - The duration of a single step in seconds.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if `dt` is not positive.
+    pub fn new(maze: Maze<W, H>, robot: Robot, dt: f64) -> Self {
+        assert!(dt > 0., "Step duration must be positive");
+
+        Physics {
+            maze,
+            robot,
+            dt,
+            time: 0.,
+            pose: Pose::start(),
+            velocity: 0.,
+            angular_velocity: 0.,
+            contacts: heapless::Vec::new(),
+        }
+    }
+
+    /// Returns the maze the mouse drives through.
+    pub fn maze(&self) -> &Maze<W, H> {
+        &self.maze
+    }
+
+    /// Returns the physical properties of the mouse.
+    pub fn robot(&self) -> &Robot {
+        &self.robot
+    }
+
+    /// Returns the duration of a single step in seconds.
+    pub fn dt(&self) -> f64 {
+        self.dt
+    }
+
+    /// Returns the amount of seconds simulated so far.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Returns the pose of the mouse.
+    pub fn pose(&self) -> Pose {
+        self.pose
+    }
+
+    /// Places the mouse at `pose`, standing still.
+    pub fn set_pose(&mut self, pose: Pose) {
+        self.pose = pose;
+        self.velocity = 0.;
+        self.angular_velocity = 0.;
+    }
+
+    /// Returns the segment the center of the mouse is in.
+    pub fn segment(&self) -> Vecu {
+        let (x, y) = self.cell();
+        Vecu { x, y }
+    }
+
+    /// Returns the forward velocity of the mouse in m/s.
+    pub fn velocity(&self) -> f64 {
+        self.velocity
+    }
+
+    /// Returns the angular velocity of the mouse in rad/s, positive when turning from east
+    /// towards south.
+    pub fn angular_velocity(&self) -> f64 {
+        self.angular_velocity
+    }
+
+    /// Returns the speed of both wheels over the floor in m/s.
+    pub fn wheel_speeds(&self) -> Wheels {
+        let turn = self.angular_velocity * self.robot.track / 2.;
+        Wheels {
+            left: self.velocity + turn,
+            right: self.velocity - turn,
+        }
+    }
+
+    /// Returns the walls the mouse collided with during the last step.
+    pub fn contacts(&self) -> &[Contact] {
+        &self.contacts
+    }
+
+    /// Returns the torque the motor delivers for the `command` in N·m, when its wheel moves
+    /// over the floor at `speed` m/s.
+    fn motor_torque(&self, command: f64, speed: f64) -> f64 {
+        let robot = &self.robot;
+        let command = command.clamp(-robot.max_torque, robot.max_torque);
+        let spin = speed / robot.wheel_radius * command.signum();
+        let limit = robot.max_torque * (1. - spin / robot.no_load_speed).clamp(0., 1.);
+
+        command.clamp(-limit, limit)
+    }
+
+    /// Returns the force in N that a wheel moving at `speed` m/s applies to the mouse when its
+    /// motor receives `command`.
+    fn wheel_force(&self, command: f64, speed: f64) -> f64 {
+        let robot = &self.robot;
+        let weight = robot.mass * GRAVITY_MS2 / 2.;
+        let traction = robot.friction * weight;
+        let force =
+            (self.motor_torque(command, speed) / robot.wheel_radius).clamp(-traction, traction);
+
+        // rolling resistance never makes the wheel roll backwards within a step
+        let stop = speed.abs() * robot.mass / 2. / self.dt;
+        let resistance = (robot.rolling_resistance * weight).min(stop);
+        force - resistance * speed.signum()
+    }
+
+    /// Simulates a single step in which the motors receive `torques`, and returns the walls the
+    /// mouse collided with.
+    ///
+    /// ### Arguments
+    ///
+    /// - `torques` - The torque requested from both motors in N·m. Positive torque drives the
+    ///   wheel forward.
+    pub fn step(&mut self, torques: Wheels) -> &[Contact] {
+        let speeds = self.wheel_speeds();
+        let left = self.wheel_force(torques.left, speeds.left);
+        let right = self.wheel_force(torques.right, speeds.right);
+
+        let acceleration = (left + right) / self.robot.mass;
+        let angular_acceleration = (left - right) * self.robot.track / 2. / self.robot.inertia;
+
+        self.velocity += acceleration * self.dt;
+        self.angular_velocity += angular_acceleration * self.dt;
+        self.pose.heading += self.angular_velocity * self.dt;
+        self.pose.position.x += self.velocity * libm::cos(self.pose.heading) * self.dt;
+        self.pose.position.y += self.velocity * libm::sin(self.pose.heading) * self.dt;
+        self.time += self.dt;
+
+        self.collide();
+        &self.contacts
+    }
+
+    /// Returns the segment the center of the mouse is in, clamped to the maze.
+    fn cell(&self) -> (u8, u8) {
+        let cell = |value: f64, size: usize| {
+            libm::floor(value / MAZE_BLOCK_M).clamp(0., (size - 1) as f64) as u8
+        };
+        (cell(self.pose.position.x, W), cell(self.pose.position.y, H))
+    }
+
+    /// Whether the wall on side `relative` of the segment at `x, y` is present or on the
+    /// border of the maze.
+    pub(crate) fn is_wall(&self, x: u8, y: u8, relative: Relative) -> bool {
+        let border = match relative {
+            Relative::North => y == 0,
+            Relative::East => x as usize + 1 == W,
+            Relative::South => y as usize + 1 == H,
+            Relative::West => x == 0,
+        };
+        border || self.maze.segment(x, y).walls[relative as usize].is_present()
+    }
+
+    /// Pushes the mouse out of every wall around it that it overlaps with, and records the
+    /// contacts.
+    fn collide(&mut self) {
+        self.contacts.clear();
+        let (cx, cy) = self.cell();
+
+        for y in cy.saturating_sub(1)..=cy.saturating_add(1).min(H as u8 - 1) {
+            for x in cx.saturating_sub(1)..=cx.saturating_add(1).min(W as u8 - 1) {
+                // every inner wall is only checked once, from the segment west or north of it
+                for relative in [
+                    Relative::North,
+                    Relative::East,
+                    Relative::South,
+                    Relative::West,
+                ] {
+                    let outer = match relative {
+                        Relative::North => y == 0,
+                        Relative::West => x == 0,
+                        _ => true,
+                    };
+                    if outer && self.is_wall(x, y, relative) {
+                        self.resolve(x, y, relative);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Pushes the mouse out of the wall on side `relative` of the segment at `x, y` if it
+    /// overlaps with it.
+    fn resolve(&mut self, x: u8, y: u8, relative: Relative) {
+        let (from, to) = wall_ends(x, y, relative);
+
+        // the closest point on the wall to the center of the mouse
+        let p = self.pose.position;
+        let closest = Vecf {
+            x: p.x.clamp(from.x, to.x),
+            y: p.y.clamp(from.y, to.y),
+        };
+        let distance = p.distance(&closest);
+        let reach = self.robot.radius + WALL_THICKNESS_M / 2.;
+        if distance >= reach || distance == 0. {
+            return;
+        }
+
+        let normal = Vecf {
+            x: (p.x - closest.x) / distance,
+            y: (p.y - closest.y) / distance,
+        };
+        self.pose.position.x += normal.x * (reach - distance);
+        self.pose.position.y += normal.y * (reach - distance);
+
+        // remove the part of the velocity that moves into the wall
+        let along =
+            libm::cos(self.pose.heading) * normal.x + libm::sin(self.pose.heading) * normal.y;
+        let into = self.velocity * along;
+        if into < 0. {
+            self.velocity *= 1. - along * along;
+        }
+
+        let _ = self.contacts.push(Contact {
+            segment: Vecu { x, y },
+            relative,
+            speed: (-into).max(0.),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::maze::{Maze, Relative};
+    use crate::physics::{Physics, Pose, Robot, Wheels, GRAVITY_MS2, WALL_THICKNESS_M};
+    use crate::vec::Vecu;
+    use crate::MAZE_BLOCK_M;
+
+    const DT: f64 = 0.001;
+
+    /// Steps `physics` for `seconds` with constant `torques`, and returns whether the mouse
+    /// collided with a wall.
+    fn run<const W: usize, const H: usize>(
+        physics: &mut Physics<W, H>,
+        torques: Wheels,
+        seconds: f64,
+    ) -> bool {
+        let mut collided = false;
+        for _ in 0..(seconds / DT) as usize {
+            collided |= !physics.step(torques).is_empty();
+        }
+        collided
+    }
+
+    #[test]
+    fn stands_still() {
+        let mut physics: Physics<16, 16> = Physics::new(Maze::new(), Robot::default(), DT);
+
+        assert!(!run(&mut physics, Wheels::default(), 1.));
+        assert_eq!(Pose::start(), physics.pose());
+        assert!((physics.time() - 1.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn accelerates() {
+        let robot = Robot::default();
+        let mut physics: Physics<16, 16> = Physics::new(Maze::new(), robot, DT);
+        let full = Wheels {
+            left: 1.,
+            right: 1.,
+        };
+
+        // the requested torque is more than the wheels can transfer to the floor
+        physics.step(full);
+        let traction = robot.friction * GRAVITY_MS2;
+        assert!(physics.velocity() <= traction * DT + 1e-9);
+
+        run(&mut physics, full, 0.2);
+        let speeds = physics.wheel_speeds();
+        assert!((speeds.left - speeds.right).abs() < 1e-9);
+        assert!(physics.velocity() > 0.5);
+        assert!(physics.velocity() < robot.no_load_speed * robot.wheel_radius);
+        assert!((physics.pose().position.x - MAZE_BLOCK_M / 2.).abs() < 1e-9);
+
+        // rolling resistance stops the mouse eventually
+        let before = physics.pose().position.y;
+        run(&mut physics, Wheels::default(), 10.);
+        assert_eq!(0., physics.velocity());
+        assert!(physics.pose().position.y > before);
+    }
+
+    #[test]
+    fn turns_in_place() {
+        let mut physics: Physics<16, 16> = Physics::new(Maze::new(), Robot::default(), DT);
+
+        run(
+            &mut physics,
+            Wheels {
+                left: 0.001,
+                right: -0.001,
+            },
+            0.1,
+        );
+        assert!(physics.angular_velocity() > 0.);
+        assert!(physics.pose().heading > Pose::start().heading);
+        assert!(physics.velocity().abs() < 1e-9);
+        assert_eq!(Vecu::new(), physics.segment());
+    }
+
+    #[test]
+    fn collides() {
+        let mut maze: Maze<4, 4> = Maze::new();
+        maze.update_walls(0, 0, [None, None, Some(true), None]);
+        let robot = Robot::default();
+        let mut physics = Physics::new(maze, robot, DT);
+
+        let forward = Wheels {
+            left: 0.002,
+            right: 0.002,
+        };
+        assert!(run(&mut physics, forward, 1.));
+
+        let contact = physics.step(forward)[0];
+        assert_eq!(Vecu::new(), contact.segment);
+        assert_eq!(Relative::South, contact.relative);
+        assert_eq!(Vecu::new(), physics.segment());
+
+        let limit = MAZE_BLOCK_M - robot.radius - WALL_THICKNESS_M / 2.;
+        assert!(physics.pose().position.y <= limit + 1e-9);
+        assert!(physics.velocity().abs() < 1e-6);
+    }
+}
@@ -0,0 +1,267 @@
+use crate::floodfill::{distances, UNREACHABLE};
+use crate::maze::{Assumption, Maze, Relative, Wall};
+use crate::path::profile::Limits;
+use crate::path::Path;
+use crate::pathfinder;
+use crate::vec::Vecu;
+
+/// The amount of time a search run may take, including returning to the origin.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Budget {
+    /// The amount of segments the mouse may move.
+    Moves(u32),
+    /// The amount of seconds the mouse may drive, estimated using [Path::profile]. Every move
+    /// is profiled on its own, starting and ending at rest.
+    Seconds(f64, Limits),
+}
+
+impl Budget {
+    /// Returns the cost of driving `path` in one go, in the unit of this budget.
+    fn cost<const W: usize, const H: usize>(&self, path: &Path<W, H>) -> f64 {
+        match self {
+            Budget::Moves(_) => path.len().saturating_sub(1) as f64,
+            Budget::Seconds(_, limits) => path.drive_time(limits),
+        }
+    }
+
+    /// Returns the total amount that may be spent, in the unit of this budget.
+    fn total(&self) -> f64 {
+        match self {
+            Budget::Moves(moves) => *moves as f64,
+            Budget::Seconds(seconds, _) => *seconds,
+        }
+    }
+}
+
+/// The result of a search run using [search].
+pub struct Report<const W: usize, const H: usize> {
+    /// The segments the mouse drove through, including the way back to the origin.
+    pub path: Path<W, H>,
+    /// Whether the mouse reached the goal before turning back.
+    pub reached_goal: bool,
+    /// The amount of the budget that was spent.
+    pub spent: f64,
+}
+
+/// Appends the shortest route from `from` to `to` over walls that are known to be open to
+/// `route`, excluding `from`, and returns whether there is such a route.
+fn known_route<const W: usize, const H: usize>(
+    maze: &Maze<W, H>,
+    from: Vecu,
+    to: Vecu,
+    route: &mut Path<W, H>,
+) -> bool {
+    let distances = distances(maze, [to], Assumption::Pessimistic);
+    let distance = |pos: Vecu| distances[pos];
+    if distance(from) == UNREACHABLE {
+        return false;
+    }
+
+    let mut pos = from;
+    while pos != to {
+        let current = maze.segment_vec(pos);
+        let next = Relative::iter()
+            .enumerate()
+            .filter(|(i, _)| current.walls[*i].is_passable(Assumption::Pessimistic))
+            .filter_map(|(_, dir)| current.relative(maze, dir))
+            .map(|segment| segment.pos())
+            .find(|next| distance(*next) < distance(pos));
+
+        match next {
+            Some(next) => pos = next,
+            None => return false,
+        }
+        route.append(pos);
+    }
+
+    true
+}
+
+/// Marks the wall between the neighbours `from` and `to` as open in `maze`,
+/// as the mouse drove through it.
+fn drive_through<const W: usize, const H: usize>(maze: &mut Maze<W, H>, from: Vecu, to: Vecu) {
+    let mut walls = [Wall::Unknown; 4];
+    walls[Relative::between(from, to).expect("Moves must be between neighbours") as usize] =
+        Wall::Open;
+    maze.update_walls(from.x, from.y, walls);
+}
+
+/// Performs a search run from the origin towards the goal of `maze` within `budget`,
+/// and returns to the origin.
+///
+/// ### Description
+///
+/// The mouse follows [pathfinder::next] and senses the walls of every segment it enters using
+/// `sense`. Before every move, it checks whether it could still return to the origin over walls
+/// that are known to be open after making the move. If that would exceed `budget`, or the goal
+/// has been reached, the mouse drives back to the origin over the best known route instead.
+/// The mouse also turns back when the goal cannot be reached through the walls known so far, or
+/// when the move and the way back would no longer fit in the driven [Path].
+///
+/// ### Implementation
+///
+/// The cost of every move is added to the amount spent so far once it has been made, such that
+/// only the planned move and the way back after it are estimated before every move.
+///
+/// ### Arguments
+///
+/// - `maze` - The known maze, which is updated with every observation.
+/// - `budget` - The amount of time the whole run may take.
+/// - `sense` - Returns the walls observed from the segment at the provided position.
+pub fn search<const W: usize, const H: usize, F>(
+    maze: &mut Maze<W, H>,
+    budget: Budget,
+    mut sense: F,
+) -> Report<W, H>
+where
+    F: FnMut(Vecu) -> [Wall; 4],
+{
+    let origin = Vecu::new();
+    let mut path: Path<W, H> = Path::new();
+    // the planned move, starting at the head of the path
+    let mut step: Path<W, H> = Path::new();
+    // the way back to the origin after the planned move
+    let mut back: Path<W, H> = Path::new();
+    // the route to the nearest unvisited segment when the pathfinder is stuck
+    let mut route: Path<W, H> = Path::new();
+    let mut reached_goal = false;
+    let mut spent = 0.;
+
+    path.append(origin);
+    maze.update_walls(origin.x, origin.y, sense(origin));
+
+    loop {
+        let head = path.head().unwrap();
+        if maze.goal().contains(head) {
+            reached_goal = true;
+            break;
+        }
+        if maze.segment_vec(head).distance == UNREACHABLE {
+            break;
+        }
+
+        step.clear();
+        step.append(head);
+        match pathfinder::next(maze, &path, &mut route) {
+            pathfinder::Result::Found(segment) => step.append(segment.pos()),
+            pathfinder::Result::Stuck(route) => step.append_all(route.as_slice()),
+        }
+        if step.len() < 2 {
+            break;
+        }
+
+        // the way back is only known after making the move, so assume it retraces the move
+        back.clear();
+        back.append_all(step.as_slice());
+        back.reverse();
+        known_route(maze, head, origin, &mut back);
+
+        let len = path.len() + step.len() - 1 + back.len() - 1;
+        if len > path.capacity() || spent + budget.cost(&step) + budget.cost(&back) > budget.total()
+        {
+            break;
+        }
+
+        let mut from = head;
+        for pos in &step.as_slice()[1..] {
+            drive_through(maze, from, *pos);
+            maze.update_walls(pos.x, pos.y, sense(*pos));
+            path.append(*pos);
+            from = *pos;
+        }
+        spent += budget.cost(&step);
+    }
+
+    let head = path.head().unwrap();
+    back.clear();
+    back.append(head);
+    assert!(
+        known_route(maze, head, origin, &mut back),
+        "Failed to find the way back"
+    );
+    path.append_all(&back.as_slice()[1..]);
+    spent += budget.cost(&back);
+
+    Report {
+        path,
+        reached_goal,
+        spent,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::maze::generate::{generate, Algorithm};
+    use crate::maze::Maze;
+    use crate::path::profile::Limits;
+    use crate::search::{search, Budget};
+    use crate::vec::Vecu;
+
+    #[test]
+    fn unlimited() {
+        let real: Maze<16, 16> = generate(Algorithm::Backtracker, 1, 0.1);
+        let mut maze: Maze<16, 16> = Maze::new();
+
+        let report = search(&mut maze, Budget::Moves(u32::MAX), |pos| {
+            real.segment(pos.x, pos.y).walls
+        });
+
+        assert!(report.reached_goal);
+        assert_eq!(Vecu::new(), report.path.head().unwrap());
+        assert_eq!((report.path.len() - 1) as f64, report.spent);
+    }
+
+    #[test]
+    fn moves() {
+        let real: Maze<16, 16> = generate(Algorithm::Prim, 2, 0.1);
+
+        for moves in [0, 1, 10, 25] {
+            let mut maze: Maze<16, 16> = Maze::new();
+            let report = search(&mut maze, Budget::Moves(moves), |pos| {
+                real.segment(pos.x, pos.y).walls
+            });
+
+            assert!(!report.reached_goal);
+            assert!(report.spent <= moves as f64);
+            assert_eq!(Vecu::new(), report.path.head().unwrap());
+        }
+    }
+
+    #[test]
+    fn unreachable() {
+        let real: Maze<3, 3> = Maze::from_ascii(
+            "\
++---+---+---+
+|           |
++---+   +   +
+|   |       |
++   +   +   +
+|   |       |
++---+---+---+
+",
+        )
+        .unwrap();
+        let mut maze: Maze<3, 3> = Maze::new();
+
+        let report = search(&mut maze, Budget::Moves(u32::MAX), |pos| {
+            real.segment(pos.x, pos.y).walls
+        });
+
+        assert!(!report.reached_goal);
+        assert_eq!(Vecu::new(), report.path.head().unwrap());
+        assert_eq!(2., report.spent);
+    }
+
+    #[test]
+    fn seconds() {
+        let real: Maze<16, 16> = generate(Algorithm::Kruskal, 3, 0.2);
+        let budget = Budget::Seconds(8., Limits::default());
+        let mut maze: Maze<16, 16> = Maze::new();
+
+        let report = search(&mut maze, budget, |pos| real.segment(pos.x, pos.y).walls);
+
+        assert!(report.spent <= 8.);
+        assert!(report.path.len() > 1);
+        assert_eq!(Vecu::new(), report.path.head().unwrap());
+    }
+}
@@ -0,0 +1,257 @@
+use crate::floodfill::UNREACHABLE;
+use crate::maze::{Maze, Relative, Wall};
+use crate::path::Path;
+use crate::pathfinder;
+use crate::pathfinder::Scratch;
+use crate::vec::Vecu;
+
+/// The walls a simulated mouse can see from a segment.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Sensors {
+    /// All four walls of the segment.
+    Adjacent,
+    /// The walls in front of, to the left of and to the right of the mouse.
+    Front,
+}
+
+/// A headless simulation of a mouse exploring a maze whose walls it does not know yet.
+///
+/// ### Description
+///
+/// The simulation holds the real maze, and the maze as known by the mouse. The mouse starts at
+/// the origin facing [Relative::South], out of the start segment. Every time it enters a
+/// segment, only the walls its [Sensors] can see are revealed, like on the robot. The way in
+/// which the mouse entered a segment is known to be open.
+pub struct Simulation<const W: usize, const H: usize> {
+    real: Maze<W, H>,
+    maze: Maze<W, H>,
+    path: Path<W, H>,
+    heading: Relative,
+    sensors: Sensors,
+    moves: u32,
+    scratch: Scratch<W, H>,
+}
+
+impl<const W: usize, const H: usize> Simulation<W, H> {
+    /// Creates a new simulation in `real` with a mouse using `sensors`, and senses the walls of
+    /// the start segment.
+    pub fn new(real: Maze<W, H>, sensors: Sensors) -> Self {
+        let mut simulation = Simulation {
+            maze: Maze::with_goal(*real.goal()),
+            real,
+            path: Path::new(),
+            heading: Relative::South,
+            sensors,
+            moves: 0,
+            scratch: Scratch::new(),
+        };
+
+        simulation.path.append(Vecu::new());
+        simulation.sense();
+        simulation
+    }
+
+    /// Returns the maze as known by the mouse.
+    pub fn maze(&self) -> &Maze<W, H> {
+        &self.maze
+    }
+
+    /// Returns the real maze.
+    pub fn real(&self) -> &Maze<W, H> {
+        &self.real
+    }
+
+    /// Returns the path the mouse has taken.
+    pub fn path(&self) -> &Path<W, H> {
+        &self.path
+    }
+
+    /// Returns the position of the mouse.
+    pub fn position(&self) -> Vecu {
+        self.path.head().expect("Failed to find path head")
+    }
+
+    /// Returns the direction the mouse is facing.
+    pub fn heading(&self) -> Relative {
+        self.heading
+    }
+
+    /// Returns the amount of segments the mouse has moved.
+    pub fn moves(&self) -> u32 {
+        self.moves
+    }
+
+    /// Whether the mouse is in the goal.
+    pub fn is_done(&self) -> bool {
+        self.maze.goal().contains(self.position())
+    }
+
+    /// Returns the walls the sensors of the mouse can see from its current position.
+    pub fn observe(&self) -> [Wall; 4] {
+        let pos = self.position();
+        let real = self.real.segment(pos.x, pos.y).walls;
+
+        match self.sensors {
+            Sensors::Adjacent => real,
+            Sensors::Front => {
+                let mut walls = [Wall::Unknown; 4];
+                for dir in [self.heading, self.heading.left(), self.heading.right()] {
+                    walls[dir as usize] = real[dir as usize];
+                }
+                walls
+            }
+        }
+    }
+
+    /// Updates the known maze with the walls the mouse can see.
+    fn sense(&mut self) {
+        let pos = self.position();
+        let walls = self.observe();
+        self.maze.update_walls(pos.x, pos.y, walls);
+    }
+
+    /// Moves the mouse to the neighbouring segment `to`, and senses its walls.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if `to` is not a neighbour of the mouse, or if there is a wall in between.
+    fn drive(&mut self, to: Vecu) {
+        let from = self.position();
+        let heading = Relative::between(from, to).expect("Mouse can only drive to neighbours");
+        assert!(
+            !self.real.segment(from.x, from.y).walls[heading as usize].is_present(),
+            "Mouse drove through a wall"
+        );
+
+        let mut entry = [Wall::Unknown; 4];
+        entry[heading.opposite() as usize] = Wall::Open;
+
+        self.heading = heading;
+        self.path.append(to);
+        self.moves += 1;
+        self.maze.update_walls(to.x, to.y, entry);
+        self.sense();
+    }
+
+    /// Performs a single step of the exploration using [pathfinder::next_with], like the robot.
+    /// When the mouse got stuck, it drives the whole route to the nearest unvisited segment.
+    /// Stepping never allocates.
+    ///
+    /// Returns whether the mouse moved, which is not the case when it is already in the goal,
+    /// when the goal cannot be reached through the walls known so far, or when the move would
+    /// not fit in its path anymore.
+    pub fn step(&mut self) -> bool {
+        let pos = self.position();
+        if self.is_done() || self.maze.segment(pos.x, pos.y).distance == UNREACHABLE {
+            return false;
+        }
+
+        let free = self.path.capacity() - self.path.len();
+        match pathfinder::next_with(&self.maze, &self.path, &mut self.scratch) {
+            Some(_) if free == 0 => return false,
+            Some(segment) => self.drive(segment.pos()),
+            None => {
+                if self.scratch.route().is_empty() || self.scratch.route().len() > free {
+                    return false;
+                }
+
+                for i in 0..self.scratch.route().len() {
+                    let pos = self.scratch.route().segment(i).unwrap();
+                    self.drive(pos);
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Steps until the mouse is in the goal, it cannot move anymore, or it has moved
+    /// `max_moves` segments. Returns whether the mouse is in the goal.
+    pub fn run(&mut self, max_moves: u32) -> bool {
+        while self.moves < max_moves && self.step() {}
+        self.is_done()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::maze::generate::{generate, Algorithm};
+    use crate::maze::{Maze, Relative};
+    use crate::sim::{Sensors, Simulation};
+    use crate::vec::Vecu;
+
+    /// Asserts that every wall known to the mouse matches the real maze.
+    fn assert_consistent<const W: usize, const H: usize>(simulation: &Simulation<W, H>) {
+        for x in 0..W as u8 {
+            for y in 0..H as u8 {
+                let known = simulation.maze().segment(x, y).walls;
+                let real = simulation.real().segment(x, y).walls;
+                for (known, real) in known.iter().zip(real.iter()) {
+                    assert!(!known.is_known() || known == real);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reaches_goal() {
+        for sensors in [Sensors::Adjacent, Sensors::Front] {
+            for seed in 0..5 {
+                let real: Maze<16, 16> = generate(Algorithm::Backtracker, seed, 0.1);
+                let mut simulation = Simulation::new(real, sensors);
+
+                assert!(simulation.run(10_000));
+                assert_consistent(&simulation);
+                assert_eq!(simulation.moves() as usize + 1, simulation.path().len());
+            }
+        }
+    }
+
+    #[test]
+    fn unreachable() {
+        let real: Maze<3, 3> = Maze::from_ascii(
+            "\
++---+---+---+
+|           |
++---+   +   +
+|   |       |
++   +   +   +
+|   |       |
++---+---+---+
+",
+        )
+        .unwrap();
+        let mut simulation = Simulation::new(real, Sensors::Adjacent);
+
+        assert!(!simulation.run(100));
+        assert!(simulation.moves() < 100);
+    }
+
+    #[test]
+    fn reveals_only_nearby_walls() {
+        let real: Maze<16, 16> = generate(Algorithm::Prim, 7, 0.);
+        let mut simulation = Simulation::new(real, Sensors::Front);
+
+        // facing south at the start, the wall behind the mouse is not seen
+        assert!(!simulation.maze().segment(0, 0).walls[Relative::North as usize].is_known());
+        assert!(simulation.maze().segment(0, 0).walls[Relative::South as usize].is_known());
+        assert!(!simulation.maze().is_known(5, 5));
+
+        simulation.step();
+        let pos = simulation.position();
+        assert_ne!(Vecu::new(), pos);
+        assert_eq!(1, simulation.moves());
+
+        // only the segments next to the path can have known walls
+        for x in 0..16u8 {
+            for y in 0..16u8 {
+                let near = (0..simulation.path().len()).any(|i| {
+                    let seg = simulation.path().segment(i).unwrap();
+                    seg.x.abs_diff(x) + seg.y.abs_diff(y) <= 1
+                });
+                let walls = simulation.maze().segment(x, y).walls;
+                assert!(near || walls.iter().all(|wall| !wall.is_known()));
+            }
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use crate::dstar::DStarLite;
+use crate::explore::explore;
+use crate::grid::BitGrid;
+use crate::maze::{Maze, Relative, Wall};
+use crate::path::profile::Limits;
+use crate::path::Path;
+use crate::search::{search, Budget};
+use crate::sim::{Sensors, Simulation};
+use crate::speedrun::{fastest_route, Costs, Route};
+use crate::vec::Vecu;
+
+/// The strategy used to explore a maze before the speed run.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Solver {
+    /// Drives to the goal with [crate::pathfinder], like the robot, using a [Simulation].
+    FloodFill,
+    /// Drives to the goal following a [DStarLite] planner.
+    DStar,
+    /// Explores until the shortest route is proven to be optimal using [explore].
+    Explore,
+    /// Drives to the goal and back to the origin using [search], without a budget.
+    Search,
+}
+
+impl Solver {
+    /// All solvers.
+    pub const ALL: [Solver; 4] = [
+        Solver::FloodFill,
+        Solver::DStar,
+        Solver::Explore,
+        Solver::Search,
+    ];
+
+    /// Returns the name of this solver.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Solver::FloodFill => "floodfill",
+            Solver::DStar => "dstar",
+            Solver::Explore => "explore",
+            Solver::Search => "search",
+        }
+    }
+
+    /// Returns the solver with `name`, or `None` if no solver has that name.
+    pub fn from_name(name: &str) -> Option<Solver> {
+        Solver::ALL.into_iter().find(|solver| solver.name() == name)
+    }
+}
+
+/// The result of exploring a maze and planning the speed run using [solve].
+pub struct Outcome<const W: usize, const H: usize> {
+    /// The maze as known after the exploration.
+    pub maze: Maze<W, H>,
+    /// The segments the mouse drove through during the exploration.
+    pub explored: Path<W, H>,
+    /// The amount of distinct segments the mouse has been in, including the start.
+    pub visited: u16,
+    /// Whether the exploration moved more segments than [Path::capacity], in which case
+    /// `explored` only holds the segments up to that capacity, and every statistic is taken
+    /// from those.
+    pub truncated: bool,
+    /// The estimated time in seconds the exploration took.
+    pub search_time: f64,
+    /// The fastest route through the explored maze, or `None` if the goal was not found.
+    pub route: Option<Route<W, H>>,
+    /// The estimated time in seconds of the speed run, or `None` if the goal was not found.
+    pub run_time: Option<f64>,
+}
+
+impl<const W: usize, const H: usize> Outcome<W, H> {
+    /// Returns the amount of segments the mouse moved during the exploration.
+    pub fn moves(&self) -> u32 {
+        self.explored.len().saturating_sub(1) as u32
+    }
+}
+
+/// Drives to the goal of `maze` following a [DStarLite] planner, and returns the driven path.
+/// Stops early when the path is full.
+fn follow_dstar<const W: usize, const H: usize, F>(
+    maze: &mut Maze<W, H>,
+    mut sense: F,
+) -> Path<W, H>
+where
+    F: FnMut(Vecu) -> [Wall; 4],
+{
+    let origin = Vecu::new();
+    let mut path = Path::new();
+    let mut planner = DStarLite::new(maze, origin);
+
+    path.append(origin);
+    planner.update_walls(maze, origin.x, origin.y, sense(origin));
+
+    while let Some(next) = planner.next(maze) {
+        if path.try_append(next).is_err() {
+            break;
+        }
+        planner.move_to(next);
+        planner.update_walls(maze, next.x, next.y, sense(next));
+    }
+
+    path
+}
+
+/// Explores `real` from the origin using `solver`, and plans the speed run through the maze as
+/// known after the exploration.
+///
+/// ### Description
+///
+/// The mouse starts in the origin facing [Relative::South], and knows nothing about the walls of
+/// `real` except its goal. Every time it enters a segment, it senses all four walls of it.
+/// Afterward, the fastest route is found with [fastest_route] and the default [Costs].
+///
+/// ### Arguments
+///
+/// - `real` - The real maze.
+/// - `solver` - The exploration strategy.
+/// - `limits` - The limits of the motion of the mouse, used to estimate both times.
+pub fn solve<const W: usize, const H: usize>(
+    real: &Maze<W, H>,
+    solver: Solver,
+    limits: &Limits,
+) -> Outcome<W, H> {
+    let sense = |pos: Vecu| real.segment(pos.x, pos.y).walls;
+    let mut maze = Maze::with_goal(*real.goal());
+    let mut truncated = false;
+
+    let explored = match solver {
+        Solver::FloodFill => {
+            let mut simulation = Simulation::new(real.clone(), Sensors::Adjacent);
+            simulation.run(u32::MAX);
+            maze = simulation.maze().clone();
+
+            let mut path = Path::new();
+            for i in 0..simulation.path().len() {
+                path.append(simulation.path().segment(i).unwrap());
+            }
+            path
+        }
+        Solver::DStar => follow_dstar(&mut maze, sense),
+        Solver::Explore => {
+            let mut path = Path::new();
+            explore(&mut maze, Vecu::new(), |pos| {
+                truncated |= path.try_append(pos).is_err();
+                sense(pos)
+            });
+            path
+        }
+        Solver::Search => search(&mut maze, Budget::Moves(u32::MAX), sense).path,
+    };
+
+    let mut visited: BitGrid<W, H> = BitGrid::new();
+    for pos in explored.as_slice() {
+        visited.set(*pos, true);
+    }
+
+    let route = fastest_route(&maze, Vecu::new(), Relative::South, &Costs::default());
+    let run_time = route.as_ref().map(|route| route.path.drive_time(limits));
+
+    Outcome {
+        maze,
+        visited: visited.count() as u16,
+        truncated,
+        search_time: explored.drive_time(limits),
+        explored,
+        route,
+        run_time,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::maze::generate::{generate, Algorithm};
+    use crate::maze::{Assumption, Maze, Relative};
+    use crate::path::profile::Limits;
+    use crate::solver::{solve, Solver};
+    use crate::speedrun::{route_cost, Costs};
+    use crate::vec::Vecu;
+
+    #[test]
+    fn names() {
+        for solver in Solver::ALL {
+            assert_eq!(Some(solver), Solver::from_name(solver.name()));
+        }
+        assert_eq!(None, Solver::from_name("random"));
+    }
+
+    #[test]
+    fn every_solver() {
+        let real: Maze<16, 16> = generate(Algorithm::Backtracker, 4, 0.2);
+        let shortest = real.shortest_route(Vecu::new(), Assumption::Pessimistic);
+
+        for solver in Solver::ALL {
+            let outcome = solve(&real, solver, &Limits::default());
+            let route = outcome.route.as_ref().unwrap();
+
+            assert_eq!(Vecu::new(), outcome.explored.segment(0).unwrap());
+            assert!(outcome.visited as u32 <= outcome.moves() + 1);
+            assert!(outcome.search_time > 0.);
+            assert!(outcome.run_time.unwrap() > 0.);
+            assert!(real.goal().contains(route.path.head().unwrap()));
+            assert_eq!(
+                route.cost,
+                route_cost(&route.path, Relative::South, &Costs::default())
+            );
+
+            // the route only passes walls that are open in the real maze
+            for pair in route.path.as_slice().windows(2) {
+                let dir = Relative::between(pair[0], pair[1]).unwrap();
+                let walls = real.segment(pair[0].x, pair[0].y).walls;
+                assert!(!walls[dir as usize].is_present());
+            }
+
+            if solver == Solver::Explore {
+                assert_eq!(
+                    shortest,
+                    outcome
+                        .maze
+                        .shortest_route(Vecu::new(), Assumption::Pessimistic)
+                );
+            }
+        }
+    }
+}
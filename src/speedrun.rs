@@ -0,0 +1,338 @@
+use crate::grid::Grid;
+use crate::heap::Heap;
+use crate::maze::{Assumption, Maze, Relative};
+use crate::path::Path;
+use crate::vec::Vecu;
+
+/// The headings of the mouse, indexed like the walls of a segment.
+const HEADINGS: [Relative; 4] = [
+    Relative::North,
+    Relative::East,
+    Relative::South,
+    Relative::West,
+];
+
+/// The cost of the movements of the mouse during a speed run,
+/// such as the time in milliseconds a movement takes.
+pub trait CostModel {
+    /// Returns the cost of driving straight through `n` segments without stopping or turning.
+    /// `n` is always at least 1.
+    fn straight(&self, n: u16) -> u32;
+
+    /// Returns the cost of turning 90 degrees to the left or right.
+    fn turn_90(&self) -> u32;
+
+    /// Returns the cost of turning around.
+    fn turn_180(&self) -> u32;
+}
+
+/// A [CostModel] where every straight costs a fixed amount to accelerate and brake,
+/// plus a fixed amount per segment.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Costs {
+    /// The cost of accelerating at the start and braking at the end of every straight.
+    pub straight: u32,
+    /// The cost of every segment of a straight.
+    pub segment: u32,
+    /// The cost of turning 90 degrees.
+    pub turn_90: u32,
+    /// The cost of turning around.
+    pub turn_180: u32,
+}
+
+impl Default for Costs {
+    fn default() -> Self {
+        Costs {
+            straight: 200,
+            segment: 100,
+            turn_90: 150,
+            turn_180: 400,
+        }
+    }
+}
+
+impl CostModel for Costs {
+    fn straight(&self, n: u16) -> u32 {
+        self.straight + self.segment * n as u32
+    }
+
+    fn turn_90(&self) -> u32 {
+        self.turn_90
+    }
+
+    fn turn_180(&self) -> u32 {
+        self.turn_180
+    }
+}
+
+/// The fastest route found by [fastest_route].
+pub struct Route<const W: usize, const H: usize> {
+    /// The segments of the route, from the start to the goal.
+    pub path: Path<W, H>,
+    /// The total cost of the route.
+    pub cost: u32,
+}
+
+/// Returns the cost of turning from `from` to `to`.
+fn turn_cost<C: CostModel>(costs: &C, from: Relative, to: Relative) -> u32 {
+    if from == to {
+        0
+    } else if from == to.opposite() {
+        costs.turn_180()
+    } else {
+        costs.turn_90()
+    }
+}
+
+/// Returns the index of the state of the mouse at `pos` with `heading`.
+fn node<const W: usize>(pos: Vecu, heading: Relative) -> usize {
+    (pos.x as usize + pos.y as usize * W) * 4 + heading as usize
+}
+
+/// Returns the position and heading of the state with index `node`.
+fn state<const W: usize>(node: usize) -> (Vecu, Relative) {
+    let cell = node / 4;
+    let pos = Vecu {
+        x: (cell % W) as u8,
+        y: (cell / W) as u8,
+    };
+
+    (pos, HEADINGS[node % 4])
+}
+
+/// Finds the fastest route from `start` to the goal of `maze`, using only walls that are known
+/// to be open.
+///
+/// ### Description
+///
+/// Unlike the shortest route, the fastest route prefers long straights and few turns, as
+/// described by `costs`. The mouse starts at `start` facing `heading`, and may drive straight
+/// ahead without turning first.
+///
+/// ### Implementation
+///
+/// Dijkstra's algorithm is performed over the states of the mouse, which are a segment and a
+/// heading. From every state, the mouse turns to a new heading, and then drives straight
+/// through `n` segments for every `n` that is possible without passing a wall that is not known
+/// to be open. As the cost of a straight depends on its entire length, a straight is always
+/// followed by a turn or the end of the route. The first state in the goal that is removed from
+/// the queue completes the fastest route, which is found by following the previous states back
+/// to the start.
+///
+/// ### Arguments
+///
+/// - `maze` - The maze.
+/// - `start` - The position of the mouse.
+/// - `heading` - The direction the mouse is facing.
+/// - `costs` - The cost of every movement.
+///
+/// ### Returns
+///
+/// The fastest route and its cost, or `None` if the goal cannot be reached through
+/// known walls.
+pub fn fastest_route<const W: usize, const H: usize, C: CostModel>(
+    maze: &Maze<W, H>,
+    start: Vecu,
+    heading: Relative,
+    costs: &C,
+) -> Option<Route<W, H>> {
+    let mut costs_so_far: Grid<[u32; 4], W, H> = Grid::new([u32::MAX; 4]);
+    let mut previous: Grid<[usize; 4], W, H> = Grid::new([usize::MAX; 4]);
+    let mut queue: Heap<u32, 4, W, H> = Heap::new();
+
+    let first = node::<W>(start, heading);
+    costs_so_far[start][heading as usize] = 0;
+    queue.push(first, 0);
+
+    while let Some((current, cost)) = queue.pop() {
+        let (pos, heading) = state::<W>(current);
+
+        if maze.goal().contains(pos) {
+            return Some(Route {
+                path: trace(maze, &previous, current),
+                cost,
+            });
+        }
+
+        for next_heading in HEADINGS {
+            if current != first && next_heading == heading {
+                continue;
+            }
+
+            let turn = cost + turn_cost(costs, heading, next_heading);
+            let mut segment = maze.segment_vec(pos);
+            let mut n = 0;
+
+            while segment.walls[next_heading as usize].is_passable(Assumption::Pessimistic) {
+                segment = match segment.relative(maze, &next_heading) {
+                    Some(segment) => segment,
+                    None => break,
+                };
+                n += 1;
+
+                let next = node::<W>(segment.pos(), next_heading);
+                let next_cost = turn + costs.straight(n);
+                let known = &mut costs_so_far[segment.pos()][next_heading as usize];
+                if next_cost < *known {
+                    *known = next_cost;
+                    previous[segment.pos()][next_heading as usize] = current;
+                    queue.push(next, next_cost);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Returns the path that ends in the state `last`, following `previous` back to the start.
+fn trace<const W: usize, const H: usize>(
+    maze: &Maze<W, H>,
+    previous: &Grid<[usize; 4], W, H>,
+    last: usize,
+) -> Path<W, H> {
+    let previous = |node: usize| {
+        let (pos, heading) = state::<W>(node);
+        previous[pos][heading as usize]
+    };
+    let mut path = Path::new();
+    let (mut pos, _) = state::<W>(last);
+    path.append(pos);
+
+    // every state was reached by driving straight from the previous state in its heading,
+    // so drive back from the end until the start is reached
+    let mut current = last;
+    while previous(current) != usize::MAX {
+        let (_, heading) = state::<W>(current);
+        let (start, _) = state::<W>(previous(current));
+        while pos != start {
+            pos = maze
+                .segment_vec(pos)
+                .relative(maze, &heading.opposite())
+                .expect("Route leaves the maze")
+                .pos();
+            path.append(pos);
+        }
+        current = previous(current);
+    }
+
+    path.reverse();
+    path.optimize();
+    path
+}
+
+/// Returns the cost of driving along `path` when the mouse starts facing `heading`.
+///
+/// ### Panics
+///
+/// Panics if two consecutive segments of `path` are not neighbours.
+pub fn route_cost<const W: usize, const H: usize, C: CostModel>(
+    path: &Path<W, H>,
+    heading: Relative,
+    costs: &C,
+) -> u32 {
+    let mut cost = 0;
+    let mut heading = heading;
+    let mut n = 0;
+
+    for i in 1..path.len() {
+        let (from, to) = (path.segment(i - 1).unwrap(), path.segment(i).unwrap());
+        let next = Relative::between(from, to).expect("Path segments must be neighbours");
+        if next != heading || n == 0 {
+            if n > 0 {
+                cost += costs.straight(n);
+            }
+            cost += turn_cost(costs, heading, next);
+            heading = next;
+            n = 0;
+        }
+        n += 1;
+    }
+
+    if n > 0 {
+        cost += costs.straight(n);
+    }
+    cost
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::goal::Goal;
+    use crate::maze::generate::{generate, Algorithm};
+    use crate::maze::{Assumption, Maze, Relative};
+    use crate::path::Path;
+    use crate::speedrun::{fastest_route, route_cost, CostModel, Costs};
+    use crate::vec::Vecu;
+
+    const OPEN: &str = "\
++---+---+---+---+
+|               |
++   +   +   +   +
+|               |
++   +   +   +   +
+|               |
++   +   +   +   +
+|               |
++---+---+---+---+
+";
+
+    #[test]
+    fn fewest_turns() {
+        let maze: Maze<4, 4> = Maze::from_ascii(OPEN).unwrap();
+        let maze = Maze::with_walls(Goal::cell(Vecu { x: 3, y: 3 }), maze);
+        let costs = Costs::default();
+
+        let route = fastest_route(&maze, Vecu::new(), Relative::South, &costs).unwrap();
+        assert_eq!(7, route.path.len());
+        assert_eq!(2 * costs.straight(3) + costs.turn_90(), route.cost);
+        assert_eq!(Vecu { x: 0, y: 3 }, route.path.segment(3).unwrap());
+        assert_eq!(route.cost, route_cost(&route.path, Relative::South, &costs));
+
+        // a staircase is just as short, but much slower
+        let mut staircase: Path<4, 4> = Path::new();
+        for i in 0..3 {
+            staircase.append(Vecu { x: i, y: i });
+            staircase.append(Vecu { x: i, y: i + 1 });
+        }
+        staircase.append(Vecu { x: 3, y: 3 });
+        assert!(route.cost < route_cost(&staircase, Relative::South, &costs));
+    }
+
+    #[test]
+    fn turn_around() {
+        let maze: Maze<4, 4> = Maze::from_ascii(OPEN).unwrap();
+        let maze = Maze::with_walls(Goal::cell(Vecu { x: 0, y: 3 }), maze);
+        let costs = Costs::default();
+
+        let route = fastest_route(&maze, Vecu::new(), Relative::North, &costs).unwrap();
+        assert_eq!(costs.turn_180() + costs.straight(3), route.cost);
+    }
+
+    #[test]
+    fn unknown_walls() {
+        let maze: Maze<4, 4> = Maze::new();
+        let route = fastest_route(&maze, Vecu::new(), Relative::South, &Costs::default());
+        assert!(route.is_none());
+    }
+
+    #[test]
+    fn generated() {
+        let costs = Costs {
+            straight: 0,
+            segment: 1,
+            turn_90: 0,
+            turn_180: 0,
+        };
+
+        for seed in 0..5 {
+            let maze: Maze<16, 16> = generate(Algorithm::Prim, seed, 0.3);
+            let route = fastest_route(&maze, Vecu::new(), Relative::South, &costs).unwrap();
+
+            // when only segments count, the fastest route is a shortest route
+            let shortest = maze.shortest_route(Vecu::new(), Assumption::Pessimistic);
+            assert_eq!(shortest, Some(route.cost as u16));
+            assert_eq!(route.cost as usize + 1, route.path.len());
+            assert_eq!(route.cost, route_cost(&route.path, Relative::South, &costs));
+        }
+    }
+}
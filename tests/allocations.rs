@@ -0,0 +1,81 @@
+use mouse::maze::generate::{generate, Algorithm};
+use mouse::maze::{ClassicMaze, Maze};
+use mouse::path::{ClassicPath, Path};
+use mouse::pathfinder::{self, Scratch};
+use mouse::sim::{Sensors, Simulation};
+use mouse::vec::Vecu;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// An allocator that counts the allocations made by the current thread.
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Runs `f`, and returns the amount of allocations it made.
+fn allocations(f: impl FnOnce()) -> usize {
+    let start = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - start
+}
+
+/// Finds the goal of `maze` from the origin using the pathfinder with `scratch`, like the robot.
+fn find_goal<const W: usize, const H: usize>(
+    maze: &mut Maze<W, H>,
+    path: &mut Path<W, H>,
+    scratch: &mut Scratch<W, H>,
+) {
+    path.append(Vecu::new());
+
+    while !maze.goal().contains(path.head().unwrap()) {
+        match pathfinder::next_with(maze, path, scratch) {
+            Some(next) => path.append(next.pos()),
+            None => path.append_all(scratch.route().as_slice()),
+        }
+    }
+}
+
+#[test]
+fn pathfinder_does_not_allocate() {
+    let real: ClassicMaze = generate(Algorithm::Backtracker, 3, 0.1);
+    let mut maze = ClassicMaze::new();
+    let mut path = ClassicPath::new();
+    let mut scratch = Scratch::new();
+    let mut route = ClassicPath::new();
+
+    // walls that force the mouse into a dead end
+    maze.update_walls(0, 0, [true, false, true, true]);
+    maze.update_walls(1, 0, [true, false, false, false]);
+    maze.update_walls(2, 0, [true, true, true, false]);
+
+    let count = allocations(|| {
+        find_goal(&mut maze, &mut path, &mut scratch);
+        pathfinder::nearest_unvisited(&maze, &path, &mut route);
+        path.optimize();
+    });
+    assert_eq!(0, count);
+    assert!(path.optimized());
+
+    let mut simulation = Simulation::new(real, Sensors::Front);
+    let count = allocations(|| {
+        simulation.run(10_000);
+    });
+    assert_eq!(0, count);
+    assert!(simulation.is_done());
+}